@@ -0,0 +1,207 @@
+//! Times the [`AdjacencyGraph`] operations solvers lean on the most, on a 64x64 arena with the
+//! bottom half taken up by the snake, against a baseline graph backed by a hash map.
+//!
+//! Run with `cargo bench --bench adjacencies`, which prints the average time of each operation for
+//! both graphs.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use bevy::{math::UVec2, utils::HashMap};
+use smallvec::SmallVec;
+use snake::{
+    adjacencies::AdjacencyGraph,
+    arena::{Direction, Directions},
+};
+
+const SIZE: UVec2 = UVec2::new(64, 64);
+
+/// How many times each operation is repeated before taking the average.
+const ITERATIONS: u32 = 1000;
+
+/// The operations being timed, implemented by both graphs.
+trait Graph: Clone {
+    fn get_neighbors(&self, pos: UVec2) -> SmallVec<[(UVec2, Direction); 4]>;
+    fn contains(&self, pos: UVec2) -> bool;
+    fn insert(&mut self, pos: UVec2);
+    fn remove(&mut self, pos: UVec2);
+}
+
+impl Graph for AdjacencyGraph {
+    fn get_neighbors(&self, pos: UVec2) -> SmallVec<[(UVec2, Direction); 4]> {
+        self.get_neighbors(pos)
+    }
+
+    fn contains(&self, pos: UVec2) -> bool {
+        self.contains(pos)
+    }
+
+    fn insert(&mut self, pos: UVec2) {
+        self.insert(pos);
+    }
+
+    fn remove(&mut self, pos: UVec2) {
+        self.remove(pos);
+    }
+}
+
+/// The graph as it was stored before it was packed into a grid, with the directions of every node
+/// in a hash map.
+#[derive(Clone)]
+struct HashMapGraph {
+    size: UVec2,
+    graph: HashMap<UVec2, Directions>,
+}
+
+impl HashMapGraph {
+    fn grid(size: UVec2) -> HashMapGraph {
+        let mut graph = Self {
+            size,
+            graph: HashMap::new(),
+        };
+
+        for pos in positions() {
+            graph.insert(pos);
+        }
+
+        graph
+    }
+
+    fn get_directions(&self, pos: UVec2) -> Directions {
+        self.graph.get(&pos).copied().unwrap_or(Directions::NONE)
+    }
+
+    /// Applies `f` to every neighbor of `pos` within the arena, along with the direction from
+    /// `pos` towards it.
+    fn for_each_neighbor(&mut self, pos: UVec2, mut f: impl FnMut(&mut HashMap<UVec2, Directions>, UVec2, Direction)) {
+        if pos.x != 0 {
+            f(&mut self.graph, pos - UVec2::new(1, 0), Direction::Left);
+        }
+
+        if pos.x < self.size.x - 1 {
+            f(&mut self.graph, pos + UVec2::new(1, 0), Direction::Right);
+        }
+
+        if pos.y != 0 {
+            f(&mut self.graph, pos - UVec2::new(0, 1), Direction::Down);
+        }
+
+        if pos.y < self.size.y - 1 {
+            f(&mut self.graph, pos + UVec2::new(0, 1), Direction::Up);
+        }
+    }
+}
+
+impl Graph for HashMapGraph {
+    fn get_neighbors(&self, pos: UVec2) -> SmallVec<[(UVec2, Direction); 4]> {
+        let dirs = self.get_directions(pos);
+        let mut neighbors = SmallVec::new();
+
+        if dirs.up() {
+            neighbors.push((pos + UVec2::new(0, 1), Direction::Up));
+        }
+
+        if dirs.down() {
+            neighbors.push((pos - UVec2::new(0, 1), Direction::Down));
+        }
+
+        if dirs.right() {
+            neighbors.push((pos + UVec2::new(1, 0), Direction::Right));
+        }
+
+        if dirs.left() {
+            neighbors.push((pos - UVec2::new(1, 0), Direction::Left));
+        }
+
+        neighbors
+    }
+
+    fn contains(&self, pos: UVec2) -> bool {
+        self.graph.contains_key(&pos)
+    }
+
+    fn insert(&mut self, pos: UVec2) {
+        let mut dirs = Directions::NONE;
+
+        self.for_each_neighbor(pos, |graph, neighbor, direction| {
+            graph.entry(neighbor).and_modify(|c| {
+                *c |= direction.flip().into();
+                dirs |= direction.into();
+            });
+        });
+
+        self.graph.insert(pos, dirs);
+    }
+
+    fn remove(&mut self, pos: UVec2) {
+        self.graph.remove(&pos);
+
+        self.for_each_neighbor(pos, |graph, neighbor, direction| {
+            graph
+                .entry(neighbor)
+                .and_modify(|c| *c &= !Directions::from(direction.flip()));
+        });
+    }
+}
+
+fn main() {
+    println!("{:<26} {:>10} {:>10}", "operation", "grid", "hash map");
+
+    let grid = time(AdjacencyGraph::grid(SIZE));
+    let hash_map = time(HashMapGraph::grid(SIZE));
+
+    for ((name, grid), (_, hash_map)) in grid.into_iter().zip(hash_map) {
+        println!("{:<26} {:>10.1?} {:>10.1?}", name, grid, hash_map);
+    }
+}
+
+/// Times every operation on `graph` once the bottom half of the arena is taken up by the snake,
+/// returning the average time per operation.
+fn time(mut graph: impl Graph) -> [(&'static str, Duration); 3] {
+    let occupied = positions().filter(|pos| pos.y < SIZE.y / 2).collect::<Vec<_>>();
+
+    for &pos in &occupied {
+        graph.remove(pos);
+    }
+
+    let cells = positions().count() as u32;
+
+    [
+        ("clone", average(|| drop(black_box(graph.clone())))),
+        (
+            "get_neighbors + contains",
+            average(|| {
+                for pos in positions() {
+                    black_box(graph.get_neighbors(black_box(pos)));
+                    black_box(graph.contains(black_box(pos)));
+                }
+            }) / cells,
+        ),
+        (
+            "insert + remove",
+            average(|| {
+                for &pos in &occupied {
+                    graph.insert(black_box(pos));
+                    graph.remove(black_box(pos));
+                }
+            }) / occupied.len() as u32,
+        ),
+    ]
+}
+
+fn positions() -> impl Iterator<Item = UVec2> {
+    (0..SIZE.y).flat_map(|y| (0..SIZE.x).map(move |x| UVec2::new(x, y)))
+}
+
+/// The average time taken by a single call of `f`.
+fn average(mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+
+    for _ in 0..ITERATIONS {
+        f();
+    }
+
+    start.elapsed() / ITERATIONS
+}
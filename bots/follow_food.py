@@ -0,0 +1,51 @@
+#!/usr/bin/env python3
+"""Example bot for the external solver, which heads straight for the food.
+
+Run it with `snake bot python3 bots/follow_food.py`. The game writes one JSON message per line to
+standard input, and the bot answers every `state` message with a line such as
+`{"direction": "up", "steps": 12}` on standard output, echoing the `steps` of the state. Logs can go
+to standard error.
+"""
+
+import json
+import sys
+
+OFFSETS = {"up": (0, 1), "down": (0, -1), "left": (-1, 0), "right": (1, 0)}
+OPPOSITE = {"up": "down", "down": "up", "left": "right", "right": "left"}
+
+
+def choose(state):
+    width, height = state["width"], state["height"]
+    head = state["body"][0]
+    # The game checks the cell ahead before moving the tail, so running into the tail loses too.
+    body = {tuple(pos) for pos in state["body"]}
+    food = state["food"] or head
+
+    def free(direction):
+        dx, dy = OFFSETS[direction]
+        x, y = head[0] + dx, head[1] + dy
+        return 0 <= x < width and 0 <= y < height and (x, y) not in body
+
+    def distance(direction):
+        dx, dy = OFFSETS[direction]
+        return abs(head[0] + dx - food[0]) + abs(head[1] + dy - food[1])
+
+    moves = [d for d in OFFSETS if d != OPPOSITE[state["direction"]] and free(d)]
+
+    if not moves:
+        return state["direction"]
+
+    return min(moves, key=distance)
+
+
+def main():
+    for line in sys.stdin:
+        message = json.loads(line)
+
+        if message["type"] == "state":
+            reply = {"direction": choose(message), "steps": message["steps"]}
+            print(json.dumps(reply), flush=True)
+
+
+if __name__ == "__main__":
+    main()
@@ -3,31 +3,102 @@ use smallvec::SmallVec;
 
 use crate::arena::{Direction, Directions};
 
+/// Bits of a cell holding the [`Directions`] of its neighbors in the graph.
+const DIRECTIONS: u8 = 0b0000_1111;
+/// Set if the cell is a node in the graph.
+const NODE: u8 = 0b0001_0000;
+/// Set if the cell holds a snake segment, whose direction is stored in [`SEGMENT_DIRECTION`].
+const SEGMENT: u8 = 0b0010_0000;
+const SEGMENT_DIRECTION: u8 = 0b1100_0000;
+const SEGMENT_SHIFT: u32 = 6;
+
+/// Graph of the cells the snake can move through, along with the direction each snake segment
+/// moves in.
+///
+/// Each cell of the arena is packed into a single byte, so cloning the graph is a single copy
+/// and lookups are plain indexing.
 #[derive(Debug, Clone)]
 pub struct AdjacencyGraph {
     size: UVec2,
-    graph: HashMap<UVec2, Directions>,
-    snake: HashMap<UVec2, Direction>,
+    cells: Vec<u8>,
 }
 
 impl AdjacencyGraph {
-    #[inline]
+    /// Creates a graph of an arena of the given `size` holding the nodes in `adjacencies`, each
+    /// connected in its given directions. Nodes outside the arena are left out.
     pub fn new(adjacencies: HashMap<UVec2, Directions>, size: UVec2) -> AdjacencyGraph {
-        Self {
+        let mut graph = Self {
             size,
-            graph: adjacencies,
-            snake: HashMap::new(),
+            cells: vec![0; (size.x * size.y) as usize],
+        };
+
+        for (pos, directions) in adjacencies {
+            if let Some(i) = graph.index(pos) {
+                graph.cells[i] = NODE | directions.bits();
+            }
+        }
+
+        graph
+    }
+
+    /// Creates a graph in which every cell of an arena of the given `size` is connected to its
+    /// neighbors.
+    pub fn grid(size: UVec2) -> AdjacencyGraph {
+        let mut cells = vec![0; (size.x * size.y) as usize];
+
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let mut directions = Directions::NONE;
+
+                if x != 0 {
+                    directions |= Directions::LEFT;
+                }
+
+                if x < size.x - 1 {
+                    directions |= Directions::RIGHT;
+                }
+
+                if y != 0 {
+                    directions |= Directions::DOWN;
+                }
+
+                if y < size.y - 1 {
+                    directions |= Directions::UP;
+                }
+
+                cells[(y * size.x + x) as usize] = NODE | directions.bits();
+            }
         }
+
+        Self { size, cells }
+    }
+
+    /// The size of the arena the graph covers.
+    pub fn size(&self) -> UVec2 {
+        self.size
     }
 
     pub fn reset(&mut self) {
-        self.graph.clear();
-        self.snake.clear();
+        self.cells.fill(0);
+    }
+
+    #[inline]
+    fn index(&self, pos: UVec2) -> Option<usize> {
+        if pos.x < self.size.x && pos.y < self.size.y {
+            Some((pos.y * self.size.x + pos.x) as usize)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn cell(&self, pos: UVec2) -> u8 {
+        self.index(pos).map_or(0, |i| self.cells[i])
     }
 
     #[inline]
     pub fn get_directions(&self, pos: UVec2) -> Directions {
-        self.graph.get(&pos).copied().unwrap_or(Directions::NONE)
+        Directions::from_bits_truncate(self.cell(pos) & DIRECTIONS)
     }
 
     pub fn get_neighbors(&self, pos: UVec2) -> SmallVec<[(UVec2, Direction); 4]> {
@@ -54,88 +125,109 @@ impl AdjacencyGraph {
     }
 
     pub fn get_segment_direction(&self, pos: UVec2) -> Option<Direction> {
-        self.snake.get(&pos).copied()
+        let cell = self.cell(pos);
+
+        if cell & SEGMENT == 0 {
+            return None;
+        }
+
+        Some(match (cell & SEGMENT_DIRECTION) >> SEGMENT_SHIFT {
+            0 => Direction::Up,
+            1 => Direction::Down,
+            2 => Direction::Left,
+            _ => Direction::Right,
+        })
     }
 
+    #[inline]
     pub fn contains(&self, pos: UVec2) -> bool {
-        self.graph.contains_key(&pos)
+        self.cell(pos) & NODE != 0
     }
 
     pub fn remove(&mut self, pos: UVec2) {
-        self.graph.remove(&pos);
+        let Some(i) = self.index(pos) else { return };
+        let width = self.size.x as usize;
+
+        self.cells[i] &= !(NODE | DIRECTIONS);
 
+        // Neighbors which aren't nodes have no directions set, so they can be cleared blindly.
         if pos.x != 0 {
-            self.graph
-                .entry(pos - UVec2::new(1, 0))
-                .and_modify(|c| *c &= !Directions::RIGHT);
+            self.cells[i - 1] &= !Directions::RIGHT.bits();
         }
 
         if pos.x < self.size.x - 1 {
-            self.graph
-                .entry(pos + UVec2::new(1, 0))
-                .and_modify(|c| *c &= !Directions::LEFT);
+            self.cells[i + 1] &= !Directions::LEFT.bits();
         }
 
         if pos.y != 0 {
-            self.graph
-                .entry(pos - UVec2::new(0, 1))
-                .and_modify(|c| *c &= !Directions::UP);
+            self.cells[i - width] &= !Directions::UP.bits();
         }
 
         if pos.y < self.size.y - 1 {
-            self.graph
-                .entry(pos + UVec2::new(0, 1))
-                .and_modify(|c| *c &= !Directions::DOWN);
+            self.cells[i + width] &= !Directions::DOWN.bits();
         }
     }
 
     pub fn insert(&mut self, pos: UVec2) {
+        let Some(i) = self.index(pos) else { return };
+        let width = self.size.x as usize;
         let mut dirs = Directions::NONE;
 
-        if pos.x != 0 {
-            self.graph.entry(pos - UVec2::new(1, 0)).and_modify(|c| {
-                *c |= Directions::RIGHT;
-                dirs |= Directions::LEFT;
-            });
+        if pos.x != 0 && self.cells[i - 1] & NODE != 0 {
+            self.cells[i - 1] |= Directions::RIGHT.bits();
+            dirs |= Directions::LEFT;
         }
 
-        if pos.x < self.size.x - 1 {
-            self.graph.entry(pos + UVec2::new(1, 0)).and_modify(|c| {
-                *c |= Directions::LEFT;
-                dirs |= Directions::RIGHT;
-            });
+        if pos.x < self.size.x - 1 && self.cells[i + 1] & NODE != 0 {
+            self.cells[i + 1] |= Directions::LEFT.bits();
+            dirs |= Directions::RIGHT;
         }
 
-        if pos.y != 0 {
-            self.graph.entry(pos - UVec2::new(0, 1)).and_modify(|c| {
-                *c |= Directions::UP;
-                dirs |= Directions::DOWN;
-            });
+        if pos.y != 0 && self.cells[i - width] & NODE != 0 {
+            self.cells[i - width] |= Directions::UP.bits();
+            dirs |= Directions::DOWN;
         }
 
-        if pos.y < self.size.y - 1 {
-            self.graph.entry(pos + UVec2::new(0, 1)).and_modify(|c| {
-                *c |= Directions::DOWN;
-                dirs |= Directions::UP;
-            });
+        if pos.y < self.size.y - 1 && self.cells[i + width] & NODE != 0 {
+            self.cells[i + width] |= Directions::DOWN.bits();
+            dirs |= Directions::UP;
         }
 
-        self.graph.insert(pos, dirs);
+        self.cells[i] = (self.cells[i] & (SEGMENT | SEGMENT_DIRECTION)) | NODE | dirs.bits();
     }
 
     pub fn insert_snake_segment(&mut self, pos: UVec2, direction: Direction) {
-        self.snake.insert(pos, direction);
+        let Some(i) = self.index(pos) else { return };
+        let bits = (direction as u8) << SEGMENT_SHIFT;
+
+        self.cells[i] = (self.cells[i] & !SEGMENT_DIRECTION) | SEGMENT | bits;
     }
 
     pub fn remove_snake_segment(&mut self, pos: UVec2) {
-        self.snake.remove(&pos);
+        let Some(i) = self.index(pos) else { return };
+
+        self.cells[i] &= !(SEGMENT | SEGMENT_DIRECTION);
+    }
+
+    fn positions(&self) -> impl Iterator<Item = UVec2> {
+        let width = self.size.x;
+        (0..self.size.x * self.size.y).map(move |i| UVec2::new(i % width, i / width))
     }
 
-    pub fn nodes(&self) -> impl Iterator<Item = (&UVec2, &Directions)> {
-        self.graph.iter()
+    /// Every node along with the directions it is connected in, in row-major order.
+    ///
+    /// The nodes are yielded by value, since their directions are packed into the grid rather than
+    /// stored on their own.
+    pub fn nodes(&self) -> impl Iterator<Item = (UVec2, Directions)> + '_ {
+        self.positions()
+            .filter(|&pos| self.contains(pos))
+            .map(|pos| (pos, self.get_directions(pos)))
     }
 
-    pub fn snake_segments(&self) -> impl Iterator<Item = (&UVec2, &Direction)> {
-        self.snake.iter()
+    /// Every snake segment along with the direction it moves in, in row-major order and by value,
+    /// as with [`AdjacencyGraph::nodes`].
+    pub fn snake_segments(&self) -> impl Iterator<Item = (UVec2, Direction)> + '_ {
+        self.positions()
+            .filter_map(|pos| self.get_segment_direction(pos).map(|dir| (pos, dir)))
     }
 }
@@ -1,11 +1,17 @@
+use std::{
+    collections::VecDeque,
+    hash::{Hash, Hasher},
+};
+
 use bevy::{prelude::*, utils::HashMap};
 use rand::seq::IteratorRandom;
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 
 use crate::{
     adjacencies::AdjacencyGraph,
     cell::{DrawCell, DrawCellTransform, ForegroundCell},
-    game::GameOver,
+    game::{GameOutcome, GameOver, GameRng},
     settings::Settings,
     snake::Snake,
 };
@@ -18,8 +24,26 @@ pub struct Arena {
     pub tail: UVec2,
     pub behind: UVec2,
     pub food: Option<UVec2>,
+    /// Whether the snake ate on the last step it moved. A step running into something doesn't move
+    /// the snake, so this is left as it was.
     pub just_ate: bool,
-    cells: Vec<Cell>,
+    /// Positions of the segments of the snake, from the head at the front to the tail at the back.
+    pub body: VecDeque<UVec2>,
+    cells: Vec<Slot>,
+    /// Incremented every time the snake moves, so that the distance of a segment to the head can
+    /// be derived from the stamp it was given when it was the head.
+    stamp: usize,
+}
+
+/// The contents of a cell as stored in the [`Arena`].
+///
+/// Snake segments store the stamp of the move in which they were the head rather than their
+/// distance to the head, so that moving the snake doesn't require updating every segment.
+#[derive(Clone, Copy)]
+enum Slot {
+    None,
+    Snake { stamp: usize },
+    Food,
 }
 
 #[derive(Clone, Copy)]
@@ -34,7 +58,8 @@ pub enum Cell {
     Food,
 }
 
-#[derive(Reflect, Debug, Clone, Copy, PartialEq, Hash, Eq, Default)]
+#[derive(Reflect, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Hash, Eq, Default)]
+#[serde(rename_all = "lowercase")]
 pub enum Direction {
     #[default]
     Up,
@@ -228,31 +253,52 @@ impl std::ops::Add<&Direction> for UVec2 {
 }
 
 impl Arena {
-    pub fn get_cell(&self, pos: IVec2) -> Option<&Cell> {
+    pub fn get_cell(&self, pos: IVec2) -> Option<Cell> {
         if pos.x < 0 || pos.y < 0 || pos.x >= self.size.x as i32 || pos.y >= self.size.y as i32 {
             return None;
         }
 
-        Some(unsafe { self.cells.get_unchecked((pos.y * self.size.x as i32 + pos.x) as usize) })
+        Some(self.get_cell_unchecked(pos.as_uvec2()))
     }
 
-    pub fn get_cell_mut(&mut self, pos: IVec2) -> Option<&mut Cell> {
-        if pos.x < 0 || pos.y < 0 || pos.x >= self.size.x as i32 || pos.y >= self.size.y as i32 {
-            return None;
-        }
+    pub fn get_cell_unchecked(&self, pos: UVec2) -> Cell {
+        let slot = unsafe { self.cells.get_unchecked((pos.y * self.size.x + pos.x) as usize) };
+        self.slot_to_cell(*slot)
+    }
+
+    /// Sets the contents of the cell at `pos`.
+    ///
+    /// This doesn't update [`Arena::body`], the adjacencies, or any other bookkeeping.
+    pub fn set_cell_unchecked(&mut self, pos: UVec2, cell: Cell) {
+        let slot = match cell {
+            Cell::None => Slot::None,
+            Cell::SnakeTail { distance } => Slot::Snake {
+                stamp: self.stamp.wrapping_sub(distance),
+            },
+            Cell::SnakeHead => Slot::Snake { stamp: self.stamp },
+            Cell::Food => Slot::Food,
+        };
 
-        Some(unsafe {
-            self.cells
-                .get_unchecked_mut((pos.y * self.size.x as i32 + pos.x) as usize)
-        })
+        unsafe {
+            *self.cells.get_unchecked_mut((pos.y * self.size.x + pos.x) as usize) = slot;
+        }
     }
 
-    pub fn get_cell_unchecked(&self, pos: UVec2) -> &Cell {
-        unsafe { self.cells.get_unchecked((pos.y * self.size.x + pos.x) as usize) }
+    #[inline]
+    fn slot_to_cell(&self, slot: Slot) -> Cell {
+        match slot {
+            Slot::None => Cell::None,
+            Slot::Snake { stamp } if stamp == self.stamp => Cell::SnakeHead,
+            Slot::Snake { stamp } => Cell::SnakeTail {
+                distance: self.stamp.wrapping_sub(stamp),
+            },
+            Slot::Food => Cell::Food,
+        }
     }
 
-    pub fn get_cell_unchecked_mut(&mut self, pos: UVec2) -> &mut Cell {
-        unsafe { self.cells.get_unchecked_mut((pos.y * self.size.x + pos.x) as usize) }
+    /// The total number of cells in the arena.
+    pub fn area(&self) -> usize {
+        self.cells.len()
     }
 
     pub fn positions(&self) -> CellPositions {
@@ -262,19 +308,11 @@ impl Arena {
         }
     }
 
-    pub fn cells(&self) -> Cells<impl Iterator<Item = &Cell>> {
-        Cells {
-            pos: IVec2::new(-1, 0),
-            width: self.size.x as i32,
-            cells: self.cells.iter(),
-        }
-    }
-
-    pub fn cells_mut(&mut self) -> Cells<impl Iterator<Item = &mut Cell>> {
+    pub fn cells(&self) -> Cells<impl Iterator<Item = Cell> + '_> {
         Cells {
             pos: IVec2::new(-1, 0),
             width: self.size.x as i32,
-            cells: self.cells.iter_mut(),
+            cells: self.cells.iter().map(|slot| self.slot_to_cell(*slot)),
         }
     }
 
@@ -284,25 +322,25 @@ impl Arena {
         let pos = pos.as_ivec2();
         let mut dirs = Directions::NONE;
 
-        if let Some(&c) = self.get_cell(pos + Direction::UP_OFFSET) {
+        if let Some(c) = self.get_cell(pos + Direction::UP_OFFSET) {
             if cell == c {
                 dirs |= Directions::UP;
             }
         }
 
-        if let Some(&c) = self.get_cell(pos + Direction::DOWN_OFFSET) {
+        if let Some(c) = self.get_cell(pos + Direction::DOWN_OFFSET) {
             if cell == c {
                 dirs |= Directions::DOWN;
             }
         }
 
-        if let Some(&c) = self.get_cell(pos + Direction::LEFT_OFFSET) {
+        if let Some(c) = self.get_cell(pos + Direction::LEFT_OFFSET) {
             if cell == c {
                 dirs |= Directions::LEFT;
             }
         }
 
-        if let Some(&c) = self.get_cell(pos + Direction::RIGHT_OFFSET) {
+        if let Some(c) = self.get_cell(pos + Direction::RIGHT_OFFSET) {
             if cell == c {
                 dirs |= Directions::RIGHT;
             }
@@ -310,40 +348,36 @@ impl Arena {
 
         dirs
     }
+
+    /// The [`Directions`] in which the snake segment at `pos` is joined to the segments next to it
+    /// in the body.
+    pub fn segment_neighbors(&self, pos: UVec2) -> Directions {
+        match self.get_cell_unchecked(pos) {
+            Cell::SnakeHead => self.neighbors_matching(pos, Cell::SnakeTail { distance: 1 }),
+            Cell::SnakeTail { distance } => {
+                let previous = if distance == 1 {
+                    Cell::SnakeHead
+                } else {
+                    Cell::SnakeTail { distance: distance - 1 }
+                };
+
+                self.neighbors_matching(pos, previous)
+                    | self.neighbors_matching(pos, Cell::SnakeTail { distance: distance + 1 })
+            },
+            Cell::None | Cell::Food => Directions::NONE,
+        }
+    }
 }
 
 pub fn setup_arena(mut commands: Commands, settings: Res<Settings>) {
     let size = settings.arena_size;
-    let mut adjacencies = HashMap::new();
-
-    for x in 0..size.x {
-        for y in 0..size.y {
-            let mut directions = Directions::NONE;
-
-            if x != 0 {
-                directions |= Directions::LEFT;
-            }
-
-            if x < size.x - 1 {
-                directions |= Directions::RIGHT;
-            }
-
-            if y != 0 {
-                directions |= Directions::DOWN;
-            }
-
-            if y < size.y - 1 {
-                directions |= Directions::UP;
-            }
-
-            adjacencies.insert(UVec2::new(x, y), directions);
-        }
-    }
 
     commands.insert_resource(Arena {
         size: settings.arena_size,
-        adjacencies: AdjacencyGraph::new(adjacencies, size),
-        cells: vec![Cell::None; (size.x * size.y) as usize],
+        adjacencies: AdjacencyGraph::grid(size),
+        cells: vec![Slot::None; (size.x * size.y) as usize],
+        stamp: 0,
+        body: VecDeque::new(),
         head: UVec2::ZERO,
         tail: UVec2::ZERO,
         behind: UVec2::ZERO,
@@ -352,6 +386,30 @@ pub fn setup_arena(mut commands: Commands, settings: Res<Settings>) {
     })
 }
 
+/// The sizes and offsets, as in [`DrawCellTransform`], of the one or two rectangles drawn for a
+/// snake segment joined to its neighbors in `dirs`.
+pub fn segment_rects(dirs: Directions) -> ((Vec2, Vec2), Option<(Vec2, Vec2)>) {
+    let mut sizes_offsets = SmallVec::<[(Vec2, Vec2); 2]>::new();
+
+    if dirs.up() {
+        sizes_offsets.push((Vec2::new(0.5, 0.75), Vec2::new(0.0, 0.125)));
+    }
+
+    if dirs.down() {
+        sizes_offsets.push((Vec2::new(0.5, 0.75), Vec2::new(0.0, -0.125)));
+    }
+
+    if dirs.left() {
+        sizes_offsets.push((Vec2::new(0.75, 0.5), Vec2::new(-0.125, 0.0)));
+    }
+
+    if dirs.right() {
+        sizes_offsets.push((Vec2::new(0.75, 0.5), Vec2::new(0.125, 0.0)));
+    }
+
+    (sizes_offsets.pop().unwrap(), sizes_offsets.pop())
+}
+
 pub fn update_cell(
     mut commands: Commands,
     arena: Res<Arena>,
@@ -359,37 +417,15 @@ pub fn update_cell(
     mut cells: Query<(&mut ForegroundCell, &mut Sprite, &mut DrawCellTransform)>,
     mut positions: Local<HashMap<UVec2, [Entity; 2]>>,
 ) {
-    let get_sizes_offsets = |dirs: Directions| -> ((Vec2, Vec2), Option<(Vec2, Vec2)>) {
-        let mut sizes_offsets = SmallVec::<[(Vec2, Vec2); 2]>::new();
-
-        if dirs.up() {
-            sizes_offsets.push((Vec2::new(0.5, 0.75), Vec2::new(0.0, 0.125)));
-        }
-
-        if dirs.down() {
-            sizes_offsets.push((Vec2::new(0.5, 0.75), Vec2::new(0.0, -0.125)));
-        }
-
-        if dirs.left() {
-            sizes_offsets.push((Vec2::new(0.75, 0.5), Vec2::new(-0.125, 0.0)));
-        }
-
-        if dirs.right() {
-            sizes_offsets.push((Vec2::new(0.75, 0.5), Vec2::new(0.125, 0.0)));
-        }
-
-        (sizes_offsets.pop().unwrap(), sizes_offsets.pop())
-    };
-
     for (pos, ty) in arena.cells() {
         if let Some(entities) = positions.get_mut(&pos) {
             let (mut contents, mut sprite, mut transform) = cells.get_mut(entities[0]).unwrap();
 
-            if *ty == contents.contents {
+            if ty == contents.contents {
                 continue;
             }
 
-            contents.contents = *ty;
+            contents.contents = ty;
 
             match ty {
                 Cell::None => {
@@ -400,18 +436,10 @@ pub fn update_cell(
                     }
                     positions.remove(&pos);
                 },
-                Cell::SnakeTail { distance, .. } => {
-                    let dirs = arena.neighbors_matching(pos, Cell::SnakeTail { distance: distance + 1 })
-                        | arena.neighbors_matching(
-                            pos,
-                            if *distance == 1 {
-                                Cell::SnakeHead
-                            } else {
-                                Cell::SnakeTail { distance: distance - 1 }
-                            },
-                        );
+                Cell::SnakeTail { .. } => {
+                    let dirs = arena.segment_neighbors(pos);
 
-                    let sizes_offsets = get_sizes_offsets(dirs);
+                    let sizes_offsets = segment_rects(dirs);
 
                     sprite.color = settings.colors.snake_tail;
                     transform.size = sizes_offsets.0 .0;
@@ -443,9 +471,9 @@ pub fn update_cell(
                     }
                 },
                 Cell::SnakeHead => {
-                    let dirs = arena.neighbors_matching(pos, Cell::SnakeTail { distance: 1 });
+                    let dirs = arena.segment_neighbors(pos);
 
-                    let sizes_offsets = get_sizes_offsets(dirs);
+                    let sizes_offsets = segment_rects(dirs);
 
                     sprite.color = settings.colors.snake_head;
                     transform.size = sizes_offsets.0 .0;
@@ -488,23 +516,15 @@ pub fn update_cell(
                 },
             }
         } else {
-            if *ty == Cell::None {
+            if ty == Cell::None {
                 continue;
             }
 
             match ty {
                 Cell::SnakeTail { distance, .. } => {
-                    let dirs = arena.neighbors_matching(pos, Cell::SnakeTail { distance: distance + 1 })
-                        | arena.neighbors_matching(
-                            pos,
-                            if *distance == 1 {
-                                Cell::SnakeHead
-                            } else {
-                                Cell::SnakeTail { distance: distance - 1 }
-                            },
-                        );
+                    let dirs = arena.segment_neighbors(pos);
 
-                    let sizes_offsets = get_sizes_offsets(dirs);
+                    let sizes_offsets = segment_rects(dirs);
 
                     positions.insert(
                         pos,
@@ -514,7 +534,7 @@ pub fn update_cell(
                                     DrawCell { pos },
                                     Sprite::from_color(settings.colors.snake_tail, Vec2::ONE),
                                     ForegroundCell {
-                                        contents: Cell::SnakeTail { distance: *distance },
+                                        contents: Cell::SnakeTail { distance },
                                     },
                                     DrawCellTransform {
                                         size: sizes_offsets.0 .0,
@@ -541,9 +561,9 @@ pub fn update_cell(
                     );
                 },
                 Cell::SnakeHead => {
-                    let dirs = arena.neighbors_matching(pos, Cell::SnakeTail { distance: 1 });
+                    let dirs = arena.segment_neighbors(pos);
 
-                    let sizes_offsets = get_sizes_offsets(dirs);
+                    let sizes_offsets = segment_rects(dirs);
 
                     positions.insert(
                         pos,
@@ -605,114 +625,174 @@ pub fn update_cell(
 }
 
 pub fn update_snake_position(mut arena: ResMut<Arena>, mut snake: ResMut<Snake>, mut game_over: ResMut<GameOver>) {
-    let mut next_head: Option<IVec2> = None;
-    let mut remove: Option<UVec2> = None;
-    arena.just_ate = false;
+    snake.steps += 1;
+    snake.steps_since_food += 1;
 
-    for pos in arena.positions() {
-        let cell = arena.get_cell_unchecked_mut(pos);
+    let head = arena.head;
+    let next_head = head.as_ivec2() + snake.direction.offset();
 
-        match cell {
-            Cell::SnakeTail { distance, .. } => {
-                if *distance >= snake.length {
-                    remove = Some(pos);
-                }
+    let next = match arena.get_cell(next_head) {
+        Some(next @ (Cell::None | Cell::Food)) => next,
+        Some(Cell::SnakeTail { .. }) | None => {
+            info!("game lost, {:?}", snake.direction);
+            game_over.0 = Some(GameOutcome::Lost);
+            return;
+        },
+        Some(Cell::SnakeHead) => unreachable!(),
+    };
 
-                *distance += 1;
+    let next_head = next_head.as_uvec2();
+    arena.just_ate = false;
 
-                if *distance == snake.length {
-                    arena.tail = pos;
-                } else if *distance == snake.length + 1 {
-                    arena.behind = pos;
-                }
-            },
-            Cell::SnakeHead => {
-                *cell = Cell::SnakeTail { distance: 1 };
-                arena.adjacencies.remove(pos);
-                arena.adjacencies.insert_snake_segment(pos, snake.direction);
+    // The tail only moves once the snake has grown to its full length, so that eating food extends
+    // the snake on the following step.
+    let remove = arena.body.len() > snake.length;
 
-                next_head = Some(pos.as_ivec2() + snake.direction.offset());
-            },
-            Cell::None | Cell::Food => (),
-        }
+    // Bumping the stamp turns the current head into the first tail segment.
+    arena.stamp = arena.stamp.wrapping_add(1);
+    arena.adjacencies.remove(head);
+    arena.adjacencies.insert_snake_segment(head, snake.direction);
+
+    if let Cell::Food = next {
+        snake.length += 1;
+        snake.steps_since_food = 0;
+        arena.food = None;
+        arena.just_ate = true;
     }
 
-    if let Some(next_head) = next_head {
-        arena.head = next_head.as_uvec2();
+    arena.set_cell_unchecked(next_head, Cell::SnakeHead);
+    arena.body.push_front(next_head);
+    arena.head = next_head;
 
-        if let Some(next) = arena.get_cell_mut(next_head) {
-            match next {
-                Cell::None => {
-                    *next = Cell::SnakeHead;
-                },
-                Cell::SnakeTail { .. } => {
-                    for (_pos, cell) in arena.cells_mut() {
-                        if let Cell::SnakeTail { distance, .. } = cell {
-                            *distance -= 1;
+    if remove {
+        let pos = arena.body.pop_back().unwrap();
+        arena.set_cell_unchecked(pos, Cell::None);
+        arena.adjacencies.insert(pos);
+        arena.adjacencies.remove_snake_segment(pos);
+        arena.behind = pos;
+    }
 
-                            if *distance == 0 {
-                                *cell = Cell::SnakeHead;
-                            }
-                        }
-                    }
+    arena.tail = *arena.body.back().unwrap();
+}
 
-                    info!("game lost, {:?}", snake.direction);
-                    game_over.0 = true;
-                    return;
-                },
-                Cell::Food => {
-                    snake.length += 1;
-                    *next = Cell::SnakeHead;
-                    arena.food = None;
-                    arena.just_ate = true;
-                },
-                Cell::SnakeHead => unreachable!(),
-            }
-        } else {
-            for (_pos, cell) in arena.cells_mut() {
-                if let Cell::SnakeTail { distance, .. } = cell {
-                    *distance -= 1;
+pub fn check_win(arena: Res<Arena>, snake: Res<Snake>, mut game_over: ResMut<GameOver>) {
+    if snake.length >= arena.area() {
+        game_over.0 = Some(GameOutcome::Won);
+    }
+}
 
-                    if *distance == 0 {
-                        *cell = Cell::SnakeHead;
-                    }
-                }
-            }
+/// Ends the game if the snake exceeds its budget of steps without eating, or returns to a state
+/// it has already been in since it last ate.
+///
+/// Since the food and the length of the snake don't change until it eats, a repeated state means
+/// a deterministic solver is stuck in a loop and will never reach the food. Players steering by
+/// hand aren't held to this.
+pub fn check_stall(
+    arena: Res<Arena>,
+    snake: Res<Snake>,
+    settings: Res<Settings>,
+    mut states: Local<StallStates>,
+    mut game_over: ResMut<GameOver>,
+) {
+    if game_over.0.is_some() {
+        return;
+    }
 
-            info!("game lost, {:?}", snake.direction);
-            game_over.0 = true;
-            return;
-        }
+    if snake.steps_since_food == 0 || snake.steps == 1 {
+        states.clear();
     }
 
-    if let Some(pos) = remove {
-        let cell = arena.get_cell_unchecked_mut(pos);
-        *cell = Cell::None;
-        arena.adjacencies.insert(pos);
-        arena.adjacencies.remove_snake_segment(pos);
+    let max_steps = settings.max_steps_without_food.unwrap_or(arena.area());
+
+    if snake.steps_since_food > max_steps || !states.insert(&arena.body, snake.direction, arena.food) {
+        info!("game stalled after {} steps without food", snake.steps_since_food);
+        game_over.0 = Some(GameOutcome::Stalled);
     }
 }
 
-pub fn check_win(arena: Res<Arena>, snake: Res<Snake>, mut game_over: ResMut<GameOver>) {
-    if snake.length >= arena.cells.len() {
-        game_over.0 = true;
+/// The states the snake has been in since it last ate, as tracked by [`check_stall`].
+///
+/// States are looked up by hash, but only count as seen once compared in full, so that a hash
+/// collision can't end a game. Rather than a copy of the body for every state, only the head
+/// positions are kept, since the body is always the last positions the head went through.
+#[derive(Default)]
+pub struct StallStates {
+    /// The body as it was when the states were last cleared, from tail to head, followed by the
+    /// head after every step since.
+    positions: Vec<UVec2>,
+    /// The states seen so far by hash, each with the number of positions up to its head and the
+    /// length of its body.
+    seen: HashMap<u64, SmallVec<[StallState; 1]>>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct StallState {
+    end: usize,
+    length: usize,
+    direction: Direction,
+    food: Option<UVec2>,
+}
+
+impl StallStates {
+    fn clear(&mut self) {
+        self.positions.clear();
+        self.seen.clear();
+    }
+
+    /// Records the state of a snake with the given `body`, from head to tail, returning whether it
+    /// is new.
+    fn insert(&mut self, body: &VecDeque<UVec2>, direction: Direction, food: Option<UVec2>) -> bool {
+        let mut hasher = rustc_hash::FxHasher::default();
+        (body, direction, food).hash(&mut hasher);
+
+        self.insert_hashed(hasher.finish(), body, direction, food)
+    }
+
+    fn insert_hashed(&mut self, hash: u64, body: &VecDeque<UVec2>, direction: Direction, food: Option<UVec2>) -> bool {
+        self.positions.push(body[0]);
+
+        // Start over from the current body if it doesn't end the positions, such as when steps went
+        // by unchecked.
+        if !self.positions.iter().rev().take(body.len()).eq(body) {
+            self.clear();
+            self.positions.extend(body.iter().rev());
+        }
+
+        let state = StallState {
+            end: self.positions.len(),
+            length: body.len(),
+            direction,
+            food,
+        };
+        let cells = |state: &StallState| &self.positions[state.end - state.length..state.end];
+
+        let states = self.seen.entry(hash).or_default();
+
+        if states.iter().any(|seen| {
+            (seen.length, seen.direction, seen.food) == (state.length, state.direction, state.food)
+                && cells(seen) == cells(&state)
+        }) {
+            return false;
+        }
+
+        states.push(state);
+        true
     }
 }
 
-pub fn spawn_food(mut arena: ResMut<Arena>) {
+pub fn spawn_food(mut arena: ResMut<Arena>, mut rng: ResMut<GameRng>) {
     if arena.food.is_some() {
         return;
     }
 
-    let mut rng = rand::thread_rng();
-
-    let (pos, cell) = arena
-        .cells_mut()
-        .filter(|(_, cell)| **cell == Cell::None)
-        .choose(&mut rng)
+    let pos = arena
+        .cells()
+        .filter(|(_, cell)| *cell == Cell::None)
+        .map(|(pos, _)| pos)
+        .choose(rng.rng())
         .unwrap();
 
-    *cell = Cell::Food;
+    arena.set_cell_unchecked(pos, Cell::Food);
     arena.food = Some(pos);
 }
 
@@ -744,7 +824,7 @@ impl std::fmt::Debug for Arena {
                 match cell {
                     Cell::None => write!(f, ".")?,
                     Cell::SnakeTail { distance, .. } => {
-                        if *distance < 10 {
+                        if distance < 10 {
                             write!(f, "{}", distance)?
                         } else {
                             write!(f, "+")?
@@ -760,3 +840,24 @@ impl std::fmt::Debug for Arena {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colliding_states_are_told_apart() {
+        let mut states = StallStates::default();
+        let body = |cells: &[(u32, u32)]| cells.iter().map(|&(x, y)| UVec2::new(x, y)).collect::<VecDeque<_>>();
+        let food = Some(UVec2::new(5, 5));
+
+        assert!(states.insert_hashed(0, &body(&[(2, 1), (1, 1), (0, 1)]), Direction::Right, food));
+        // A different state with the same hash is still new.
+        assert!(states.insert_hashed(0, &body(&[(2, 2), (2, 1), (1, 1)]), Direction::Up, food));
+        assert!(states.insert_hashed(0, &body(&[(1, 2), (2, 2), (2, 1)]), Direction::Left, food));
+        assert!(states.insert_hashed(0, &body(&[(1, 1), (1, 2), (2, 2)]), Direction::Down, food));
+        // Only returning to an earlier state counts as seen.
+        assert!(states.insert_hashed(0, &body(&[(2, 1), (1, 1), (1, 2)]), Direction::Right, food));
+        assert!(!states.insert_hashed(0, &body(&[(2, 2), (2, 1), (1, 1)]), Direction::Up, food));
+    }
+}
@@ -1,6 +1,12 @@
 use bevy::{prelude::*, window::PrimaryWindow};
 
-use crate::{arena::Cell, settings::Settings};
+use crate::{
+    arena::{Arena, Cell},
+    game::{PreviousTime, TimeSteps},
+    settings::Settings,
+    snake::Snake,
+    theme::ThemedCell,
+};
 
 #[derive(Component)]
 pub struct DrawCell {
@@ -20,6 +26,10 @@ pub struct ForegroundCell {
     pub contents: Cell,
 }
 
+/// The end of the tail which is still drawn over the cell it just left, see [`interpolate_snake`].
+#[derive(Component)]
+pub struct TailStub;
+
 pub fn setup_cells(mut commands: Commands, settings: Res<Settings>) {
     for x in 0..settings.arena_size.x {
         for y in 0..settings.arena_size.y {
@@ -37,6 +47,18 @@ pub fn setup_cells(mut commands: Commands, settings: Res<Settings>) {
             ));
         }
     }
+
+    // Sized to nothing by `update_cell_transform`, unless `interpolate_snake` sizes it after that.
+    commands.spawn((
+        DrawCell { pos: UVec2::ZERO },
+        Sprite::from_color(settings.colors.snake_tail, Vec2::ONE),
+        ForegroundCell { contents: Cell::None },
+        DrawCellTransform {
+            size: Vec2::ZERO,
+            offset: Vec2::ZERO,
+        },
+        TailStub,
+    ));
 }
 
 pub fn update_cell_transform(
@@ -49,15 +71,7 @@ pub fn update_cell_transform(
         Option<&ForegroundCell>,
     )>,
 ) {
-    let window = windows.single();
-    let mut window_size = window.size();
-
-    let max_dim = if window_size.x > window_size.y { 0 } else { 1 };
-    let aspect = window_size[1 - max_dim] / window_size[max_dim];
-
-    let mut tile_size = window_size / settings.arena_size.as_vec2();
-    tile_size[max_dim] *= aspect;
-    window_size[max_dim] *= aspect;
+    let layout = Layout::new(windows.single(), settings.arena_size);
 
     for (cell, mut transform, cell_transform, fg) in &mut cells {
         let (size, offset) = if let Some(t) = cell_transform {
@@ -66,11 +80,188 @@ pub fn update_cell_transform(
             (Vec2::ONE, Vec2::ZERO)
         };
 
-        transform.scale = Vec3::new(tile_size.x * size.x, tile_size.y * size.y, 1.0);
-
-        let pos = (cell.pos.as_vec2() + offset) / settings.arena_size.as_vec2() * window_size - (window_size / 2.0)
-            + (tile_size / 2.0);
         let z = if fg.is_some() { 1.0 } else { 0.0 };
+        layout.place(&mut transform, cell.pos, (size, offset), z);
+    }
+}
+
+/// Where the snake was at its last step, to tell whether its head and tail moved by a single cell.
+#[derive(Default)]
+pub struct SnakeMotion {
+    steps: usize,
+    head: UVec2,
+    tail: UVec2,
+    head_moved: bool,
+    /// The cell the tail left, unless it stayed where it was.
+    tail_left: Option<UVec2>,
+}
+
+/// The rectangles drawn by [`update_cell`](crate::arena::update_cell), leaving out the tail stub and
+/// the sprites of atlas themes, which aren't interpolated.
+type FlatCells = (Without<TailStub>, Without<ThemedCell>);
+
+/// Slides the head of the snake into its cell and the end of the tail out of the cell it left over
+/// the step interval, when [`Settings::interpolate`] is set.
+///
+/// This runs after [`update_cell_transform`], and only resizes the rectangles at either end of the
+/// snake so that the body stays connected.
+#[allow(clippy::too_many_arguments)]
+pub fn interpolate_snake(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    settings: Res<Settings>,
+    arena: Res<Arena>,
+    snake: Res<Snake>,
+    time: Res<Time>,
+    steps: Res<TimeSteps>,
+    previous: Res<PreviousTime>,
+    mut motion: Local<SnakeMotion>,
+    mut cells: Query<(&DrawCell, &DrawCellTransform, &ForegroundCell, &mut Transform), FlatCells>,
+    mut stub: Query<&mut Transform, With<TailStub>>,
+) {
+    if snake.steps != motion.steps {
+        let single_step = snake.steps == motion.steps + 1;
+
+        motion.head_moved = single_step && arena.head != motion.head;
+        motion.tail_left = (single_step && arena.tail != motion.tail).then_some(motion.tail);
+        motion.steps = snake.steps;
+        motion.head = arena.head;
+        motion.tail = arena.tail;
+    }
+
+    if !settings.interpolate {
+        return;
+    }
+
+    let layout = Layout::new(windows.single(), settings.arena_size);
+    let progress = previous.progress(&time, &steps);
+
+    if let (true, Some(&neck)) = (motion.head_moved, arena.body.get(1)) {
+        let forward = arena.head.as_vec2() - neck.as_vec2();
+
+        for (cell, cell_transform, fg, mut transform) in &mut cells {
+            let span = if cell.pos == arena.head && fg.contents == Cell::SnakeHead {
+                head_span(forward, progress)
+            } else if cell.pos == neck && cell_transform.offset.dot(forward) > 0.0 {
+                neck_span(forward, progress)
+            } else {
+                continue;
+            };
+
+            layout.place(&mut transform, cell.pos, span, 1.0);
+        }
+    }
+
+    if let Some(left) = motion.tail_left {
+        let backward = left.as_vec2() - arena.tail.as_vec2();
+        let span = segment_span(backward, 0.0, 1.25 * (1.0 - progress));
+
+        layout.place(&mut stub.single_mut(), arena.tail, span, 1.0);
+    }
+}
+
+/// The part of the head which has moved into its cell along `forward`. The head grows out of the
+/// edge it shares with the neck, once the neck has grown up to that edge at a quarter of the way.
+fn head_span(forward: Vec2, progress: f32) -> (Vec2, Vec2) {
+    segment_span(forward, -0.5, progress - 0.75)
+}
+
+/// The part of the neck reaching out of its center towards the head along `forward`.
+fn neck_span(forward: Vec2, progress: f32) -> (Vec2, Vec2) {
+    segment_span(forward, -0.25, (progress + 0.25).min(0.5))
+}
+
+/// The size and offset of a rectangle as wide as the snake, which runs along `dir` from `start` to
+/// `end`, measured from the center of a cell.
+fn segment_span(dir: Vec2, start: f32, end: f32) -> (Vec2, Vec2) {
+    let length = (end - start).max(0.0);
+    let size = if dir.x != 0.0 {
+        Vec2::new(length, 0.5)
+    } else {
+        Vec2::new(0.5, length)
+    };
+
+    (size, dir * (start + end) / 2.0)
+}
+
+/// How cells of the arena are laid out in the window, keeping them square.
+struct Layout {
+    arena_size: Vec2,
+    window_size: Vec2,
+    tile_size: Vec2,
+}
+
+impl Layout {
+    fn new(window: &Window, arena_size: UVec2) -> Layout {
+        let mut window_size = window.size();
+
+        let max_dim = if window_size.x > window_size.y { 0 } else { 1 };
+        let aspect = window_size[1 - max_dim] / window_size[max_dim];
+
+        let mut tile_size = window_size / arena_size.as_vec2();
+        tile_size[max_dim] *= aspect;
+        window_size[max_dim] *= aspect;
+
+        Self {
+            arena_size: arena_size.as_vec2(),
+            window_size,
+            tile_size,
+        }
+    }
+
+    /// Places a rectangle with the given size and offset within the cell at `pos`, as in
+    /// [`DrawCellTransform`].
+    fn place(&self, transform: &mut Transform, pos: UVec2, (size, offset): (Vec2, Vec2), z: f32) {
+        transform.scale = Vec3::new(self.tile_size.x * size.x, self.tile_size.y * size.y, 1.0);
+
+        let pos = (pos.as_vec2() + offset) / self.arena_size * self.window_size - (self.window_size / 2.0)
+            + (self.tile_size / 2.0);
         transform.translation = Vec3::new(pos.x, pos.y, z);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The distances along `dir` from the center of the cell to both ends of a span.
+    fn ends(dir: Vec2, (size, offset): (Vec2, Vec2)) -> (f32, f32) {
+        let center = offset.dot(dir);
+        let length = size.dot(dir.abs());
+
+        (center - length / 2.0, center + length / 2.0)
+    }
+
+    #[test]
+    fn segments_span_from_start_to_end() {
+        assert_eq!(
+            segment_span(Vec2::X, -0.5, 0.25),
+            (Vec2::new(0.75, 0.5), Vec2::new(-0.125, 0.0))
+        );
+        assert_eq!(
+            segment_span(Vec2::NEG_Y, 0.0, 0.5),
+            (Vec2::new(0.5, 0.5), Vec2::new(0.0, -0.25))
+        );
+
+        // Spans ending before they start are clamped to nothing.
+        assert_eq!(segment_span(Vec2::Y, 0.25, -0.5).0, Vec2::new(0.5, 0.0));
+    }
+
+    #[test]
+    fn head_grows_once_the_neck_reaches_it() {
+        for forward in [Vec2::X, Vec2::NEG_X, Vec2::Y, Vec2::NEG_Y] {
+            // Right after a step, the neck reaches a quarter past its center, and the head isn't
+            // drawn at all.
+            assert_eq!(ends(forward, neck_span(forward, 0.0)), (-0.25, 0.25));
+            assert_eq!(head_span(forward, 0.0).0.dot(forward.abs()), 0.0);
+
+            // A quarter of the way, the neck reaches the edge it shares with the head.
+            assert_eq!(ends(forward, neck_span(forward, 0.25)), (-0.25, 0.5));
+            assert_eq!(head_span(forward, 0.25).0.dot(forward.abs()), 0.0);
+
+            // After that, the head grows out of that edge while the neck stays put.
+            assert_eq!(ends(forward, neck_span(forward, 0.5)), (-0.25, 0.5));
+            assert_eq!(ends(forward, head_span(forward, 0.5)), (-0.5, -0.25));
+            assert_eq!(ends(forward, head_span(forward, 1.0)), (-0.5, 0.25));
+        }
+    }
+}
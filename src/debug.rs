@@ -2,7 +2,10 @@ use bevy::{color::palettes::css as colors, prelude::*, window::PrimaryWindow};
 
 use crate::{
     arena::{Arena, Direction},
-    solver::Solver,
+    solver::{
+        analysis::{Components, Cuts},
+        Solver,
+    },
     ui::Configuration,
 };
 
@@ -20,7 +23,7 @@ pub fn debug_adjacencies(
     let half_cell = cell_size.0 / 2.0;
 
     for (pos, dirs) in arena.adjacencies.nodes() {
-        let center = get_cell_center(*pos, arena.size, cell_size);
+        let center = get_cell_center(pos, arena.size, cell_size);
 
         if dirs.up() {
             gizmos.line_2d(center, center + Vec2::new(0.0, half_cell.y), colors::PINK)
@@ -151,12 +154,61 @@ pub fn debug_solver_tables(
     }
 }
 
+/// Crosses out free cells the head can't reach, and marks the articulation points and bridges of
+/// the free cells.
+pub fn debug_analysis(
+    mut gizmos: Gizmos,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    arena: Res<Arena>,
+    config: Res<Configuration>,
+) {
+    if !config.debug_analysis {
+        return;
+    }
+
+    let cell_size = compute_cell_size(windows.single(), arena.size);
+    let half_cell = cell_size.0 / 2.0;
+
+    let components = Components::new(&arena.adjacencies);
+    let head = components.label(arena.head);
+
+    for (pos, _) in arena.adjacencies.nodes() {
+        if components.label(pos) != head {
+            let center = get_cell_center(pos, arena.size, cell_size);
+            let corner = half_cell / 2.0;
+
+            gizmos.line_2d(center - corner, center + corner, colors::GRAY);
+            gizmos.line_2d(
+                center + corner * Vec2::new(-1.0, 1.0),
+                center - corner * Vec2::new(-1.0, 1.0),
+                colors::GRAY,
+            );
+        }
+    }
+
+    let cuts = Cuts::new(&arena.adjacencies);
+
+    for pos in cuts.articulation_points {
+        let center = get_cell_center(pos, arena.size, cell_size);
+        gizmos.circle_2d(center, half_cell.x / 2.0, colors::ORANGE);
+    }
+
+    for (pos, dir) in cuts.bridges {
+        let center = get_cell_center(pos, arena.size, cell_size);
+        gizmos.line_2d(
+            center,
+            center + dir.offset().as_vec2() * cell_size.0,
+            colors::ORANGE_RED,
+        );
+    }
+}
+
 pub fn debug_snake_segments(mut gizmos: Gizmos, windows: Query<&Window, With<PrimaryWindow>>, arena: Res<Arena>) {
     let cell_size = compute_cell_size(windows.single(), arena.size);
     let half_cell = cell_size.0 / 2.0;
 
     for (pos, dir) in arena.adjacencies.snake_segments() {
-        let center = get_cell_center(*pos, arena.size, cell_size);
+        let center = get_cell_center(pos, arena.size, cell_size);
 
         match dir {
             Direction::Up => gizmos.arrow_2d(center, center + Vec2::new(0.0, half_cell.y), colors::DEEP_PINK),
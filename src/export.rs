@@ -0,0 +1,219 @@
+//! Renders games to images without opening a window, as an animated GIF or as numbered PNG frames.
+
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{
+    color::{Color, ColorToPacked},
+    math::{UVec2, Vec2},
+};
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, ImageResult, Rgba, RgbaImage,
+};
+
+use crate::{
+    arena::{self, Arena, Cell},
+    headless::HeadlessGame,
+    settings::ColorSettings,
+};
+
+/// Where the frames of an exported game are written to.
+pub enum Frames {
+    /// A single animated GIF, which loops forever.
+    Gif {
+        encoder: GifEncoder<BufWriter<File>>,
+        delay: Delay,
+    },
+    /// Numbered PNG files in a directory, starting at `00000.png`.
+    Png { dir: PathBuf, count: usize },
+}
+
+impl Frames {
+    /// Writes an animated GIF to `path` if it ends in `.gif`, showing each frame for `delay`, and
+    /// PNG frames to the directory at `path` otherwise, which is created if needed.
+    pub fn create(path: &Path, delay: Duration) -> ImageResult<Frames> {
+        if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gif")) {
+            let mut encoder = GifEncoder::new_with_speed(BufWriter::new(File::create(path)?), 10);
+            encoder.set_repeat(Repeat::Infinite)?;
+
+            Ok(Frames::Gif {
+                encoder,
+                delay: Delay::from_saturating_duration(delay),
+            })
+        } else {
+            fs::create_dir_all(path)?;
+
+            Ok(Frames::Png {
+                dir: path.to_path_buf(),
+                count: 0,
+            })
+        }
+    }
+
+    pub fn push(&mut self, frame: RgbaImage) -> ImageResult<()> {
+        match self {
+            Frames::Gif { encoder, delay } => encoder.encode_frame(Frame::from_parts(frame, 0, 0, *delay)),
+            Frames::Png { dir, count } => {
+                frame.save(dir.join(format!("{:05}.png", count)))?;
+                *count += 1;
+
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Plays `game` until it ends, rendering a frame before the first step and after every step.
+///
+/// Returns the number of frames rendered.
+pub fn export(
+    game: &mut HeadlessGame,
+    frames: &mut Frames,
+    colors: &ColorSettings,
+    cell_size: u32,
+) -> ImageResult<usize> {
+    let mut count = 0;
+
+    loop {
+        frames.push(render(game.arena(), colors, cell_size))?;
+        count += 1;
+
+        if game.outcome().is_some() {
+            return Ok(count);
+        }
+
+        game.step();
+    }
+}
+
+/// Draws the arena with `cell_size` pixels per cell, laid out and coloured in the same way as the
+/// window draws it with [`setup_cells`](crate::cell::setup_cells) and
+/// [`update_cell`](arena::update_cell).
+pub fn render(arena: &Arena, colors: &ColorSettings, cell_size: u32) -> RgbaImage {
+    let mut image = RgbaImage::new(arena.size.x * cell_size, arena.size.y * cell_size);
+
+    for (pos, cell) in arena.cells() {
+        let background = if (pos.x + pos.y) % 2 == 0 {
+            colors.background_light
+        } else {
+            colors.background_dark
+        };
+
+        fill(&mut image, cell_size, pos, (Vec2::ONE, Vec2::ZERO), background);
+
+        let color = match cell {
+            Cell::None => continue,
+            Cell::Food => {
+                fill(&mut image, cell_size, pos, (Vec2::splat(0.5), Vec2::ZERO), colors.food);
+                continue;
+            },
+            Cell::SnakeHead => colors.snake_head,
+            Cell::SnakeTail { .. } => colors.snake_tail,
+        };
+
+        let (first, second) = arena::segment_rects(arena.segment_neighbors(pos));
+
+        for rect in [Some(first), second].into_iter().flatten() {
+            fill(&mut image, cell_size, pos, rect, color);
+        }
+    }
+
+    image
+}
+
+/// Fills a rectangle with the given size and offset within the cell at `pos`, as in
+/// [`DrawCellTransform`](crate::cell::DrawCellTransform).
+fn fill(image: &mut RgbaImage, cell_size: u32, pos: UVec2, (size, offset): (Vec2, Vec2), color: Color) {
+    let cell_size = cell_size as f32;
+    let rows = image.height() as f32 / cell_size;
+
+    // Image rows go down, while arena rows go up.
+    let center = Vec2::new(pos.x as f32 + 0.5 + offset.x, rows - pos.y as f32 - 0.5 - offset.y) * cell_size;
+    let min = (center - size * cell_size / 2.0).round().as_uvec2();
+    let max = (center + size * cell_size / 2.0).round().as_uvec2();
+    let pixel = Rgba(color.to_srgba().to_u8_array());
+
+    for y in min.y..max.y {
+        for x in min.x..max.x {
+            image.put_pixel(x, y, pixel);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        settings::Settings,
+        solver::{hamilton::HamiltonSolver, Solver},
+    };
+
+    const CELL_SIZE: u32 = 8;
+
+    fn game(size: UVec2) -> HeadlessGame {
+        let settings = Settings {
+            arena_size: size,
+            seed: Some(0),
+            ..Default::default()
+        };
+
+        HeadlessGame::new(settings, Solver::new("hamilton", HamiltonSolver::default()))
+    }
+
+    /// The color at `offset` from the center of the cell at `pos`, with the cell spanning from
+    /// `-0.5` to `0.5`.
+    fn color_at(image: &RgbaImage, pos: UVec2, offset: Vec2) -> Color {
+        let rows = image.height() / CELL_SIZE;
+        let x = (pos.x as f32 + 0.5 + offset.x) * CELL_SIZE as f32;
+        let y = ((rows - pos.y) as f32 - 0.5 - offset.y) * CELL_SIZE as f32;
+        let Rgba([r, g, b, _]) = *image.get_pixel(x as u32, y as u32);
+
+        Color::srgb_u8(r, g, b)
+    }
+
+    #[test]
+    fn frames_match_the_window() {
+        let game = game(UVec2::new(8, 4));
+        let arena = game.arena();
+        let colors = ColorSettings::default();
+        let image = render(arena, &colors, CELL_SIZE);
+
+        assert_eq!(image.dimensions(), (64, 32));
+
+        // The snake starts out facing left, with its head joined to the body on the right.
+        let head = arena.head;
+        assert_eq!(color_at(&image, head, Vec2::new(0.2, 0.0)), colors.snake_head);
+        assert_eq!(color_at(&image, head, Vec2::new(-0.4, 0.0)), colors.background_dark);
+        assert_eq!(color_at(&image, head, Vec2::new(0.0, 0.4)), colors.background_dark);
+
+        let tail = arena.tail;
+        assert_eq!(color_at(&image, tail, Vec2::new(-0.4, 0.0)), colors.snake_tail);
+        assert_eq!(color_at(&image, tail, Vec2::new(0.4, 0.0)), colors.background_dark);
+
+        // Food takes up half of its cell.
+        let food = arena.food.unwrap();
+        assert_eq!(color_at(&image, food, Vec2::ZERO), colors.food);
+        assert_eq!(color_at(&image, food, Vec2::new(0.4, 0.4)), colors.background_dark);
+    }
+
+    #[test]
+    fn games_export_to_numbered_frames() {
+        let dir = std::env::temp_dir().join(format!("snake-frames-{}", std::process::id()));
+        let mut game = game(UVec2::new(4, 4));
+        let mut frames = Frames::create(&dir, Duration::ZERO).unwrap();
+
+        let count = export(&mut game, &mut frames, &ColorSettings::default(), CELL_SIZE).unwrap();
+        let files = fs::read_dir(&dir).unwrap().count();
+        let last = image::open(dir.join(format!("{:05}.png", count - 1))).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(count, game.snake().steps + 1);
+        assert_eq!(files, count);
+        assert_eq!(last.width(), 4 * CELL_SIZE);
+    }
+}
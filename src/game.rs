@@ -1,9 +1,18 @@
 use std::{
+    hash::{BuildHasher, Hasher},
     num::{NonZero, NonZeroU32},
     time::Duration,
 };
 
-use bevy::{app::MainScheduleOrder, ecs::schedule::ScheduleLabel, prelude::*};
+use bevy::{
+    app::MainScheduleOrder,
+    ecs::schedule::ScheduleLabel,
+    prelude::*,
+    utils::{FixedState, SystemTime},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{settings::Settings, snake::Snake};
 
 #[derive(States, Clone, Eq, PartialEq, Hash, Debug, Default)]
 pub enum GameState {
@@ -20,8 +29,88 @@ pub enum GameMode {
     Computer,
 }
 
+/// How a game ended.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameOutcome {
+    /// The snake filled the entire arena.
+    Won,
+    /// The snake ran into a wall or itself.
+    Lost,
+    /// The snake went too long without eating, or repeated a previous state without eating in
+    /// between.
+    Stalled,
+}
+
+/// Set by the [`SolveStep`] systems once the current game has ended.
 #[derive(Resource, Default)]
-pub struct GameOver(pub bool);
+pub struct GameOver(pub Option<GameOutcome>);
+
+/// Sent once a game has ended, after its outcome has been recorded in [`Statistics`].
+#[derive(Event, Debug, Clone, Copy)]
+pub struct GameFinished {
+    pub outcome: GameOutcome,
+    pub steps: usize,
+    pub length: usize,
+}
+
+/// Random number generator used for everything random within a game, reseeded at the start of
+/// every game so that a game can be replayed from its seed.
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> GameRng {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The seed of the current game.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// Running totals of the outcomes of every game played since startup.
+#[derive(Reflect, Resource, Default, Debug)]
+#[reflect(Resource)]
+pub struct Statistics {
+    pub games: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub stalls: usize,
+    pub last_outcome: Option<GameOutcome>,
+    /// The number of steps taken in the last finished game.
+    pub last_steps: usize,
+}
+
+impl Statistics {
+    pub fn record(&mut self, outcome: GameOutcome, steps: usize) {
+        self.games += 1;
+        self.last_outcome = Some(outcome);
+        self.last_steps = steps;
+
+        match outcome {
+            GameOutcome::Won => self.wins += 1,
+            GameOutcome::Lost => self.losses += 1,
+            GameOutcome::Stalled => self.stalls += 1,
+        }
+    }
+}
 
 pub struct SchedulesPlugin;
 
@@ -30,6 +119,10 @@ impl Plugin for SchedulesPlugin {
         app.init_resource::<TimeSteps>()
             .init_resource::<PreviousTime>()
             .init_resource::<LastSolverInput>()
+            .init_resource::<Statistics>()
+            .init_resource::<GameRng>()
+            .register_type::<Statistics>()
+            .add_event::<GameFinished>()
             .init_schedule(SolveStep)
             .init_schedule(Draw);
 
@@ -38,7 +131,8 @@ impl Plugin for SchedulesPlugin {
         order.insert_after(Update, Solve);
         order.insert_after(Solve, Draw);
 
-        app.add_systems(Solve, run_solve_schedule);
+        app.add_systems(Solve, run_solve_schedule)
+            .add_systems(OnExit(GameState::Stopped), reseed_rng);
     }
 }
 
@@ -81,8 +175,44 @@ impl LastSolverInput {
     }
 }
 
+/// The elapsed time at which the last step was taken, when stepping at an interval.
 #[derive(Resource, Default)]
-struct PreviousTime(Duration);
+pub struct PreviousTime(Duration);
+
+impl PreviousTime {
+    /// How far the game is from its last step to its next one, from `0.0` right after a step to
+    /// `1.0` once the next step is due.
+    ///
+    /// This is always `1.0` when stepping every frame.
+    pub fn progress(&self, time: &Time, steps: &TimeSteps) -> f32 {
+        match steps.interval {
+            Some(interval) if interval > 0.0 => {
+                (time.elapsed().saturating_sub(self.0).as_secs_f32() / interval).min(1.0)
+            },
+            _ => 1.0,
+        }
+    }
+}
+
+/// Starts the next game with a fresh seed, or the one from [`Settings::seed`] if set.
+pub fn reseed_rng(settings: Res<Settings>, mut rng: ResMut<GameRng>) {
+    let seed = settings.seed.unwrap_or_else(random_seed);
+    *rng = GameRng::new(seed);
+}
+
+/// Picks a seed from the current time.
+///
+/// Unlike the operating system's entropy source, the clock is also available in browsers.
+pub fn random_seed() -> u64 {
+    let time = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+
+    // Hash the time, so that seeds picked shortly after each other differ in all of their bits.
+    let mut hasher = FixedState.build_hasher();
+    hasher.write_u128(time.as_nanos());
+    hasher.finish()
+}
 
 fn run_solve_schedule(world: &mut World) {
     let state = world.resource::<State<GameState>>();
@@ -117,11 +247,7 @@ fn run_solve_schedule(world: &mut World) {
         world.resource_mut::<LastSolverInput>().last = None;
 
         for _ in 0..substeps - 1 {
-            let mut game_over = world.resource_mut::<GameOver>();
-
-            if game_over.0 {
-                game_over.0 = false;
-                world.resource_mut::<NextState<GameState>>().set(GameState::Stopped);
+            if end_game(world) {
                 break;
             }
 
@@ -129,10 +255,68 @@ fn run_solve_schedule(world: &mut World) {
         }
     });
 
-    let mut game_over = world.resource_mut::<GameOver>();
+    end_game(world);
+}
+
+/// Stops the game and records its outcome if it has ended, returning whether it did.
+fn end_game(world: &mut World) -> bool {
+    let Some(outcome) = world.resource_mut::<GameOver>().0.take() else {
+        return false;
+    };
+
+    let snake = world.resource::<Snake>();
+    let (steps, length) = (snake.steps, snake.length);
+
+    world.resource_mut::<Statistics>().record(outcome, steps);
+    world.send_event(GameFinished { outcome, steps, length });
+    world.resource_mut::<NextState<GameState>>().set(GameState::Stopped);
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(elapsed: Duration) -> Time {
+        let mut time = Time::default();
+        time.advance_by(elapsed);
+        time
+    }
+
+    fn steps(interval: Option<f32>) -> TimeSteps {
+        TimeSteps {
+            interval,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn progress_runs_from_the_last_step_to_the_next() {
+        let previous = PreviousTime(Duration::from_secs(2));
+
+        assert_eq!(previous.progress(&time(Duration::from_secs(2)), &steps(Some(1.0))), 0.0);
+        assert_eq!(
+            previous.progress(&time(Duration::from_millis(2250)), &steps(Some(1.0))),
+            0.25
+        );
+        assert_eq!(
+            previous.progress(&time(Duration::from_millis(2500)), &steps(Some(2.0))),
+            0.25
+        );
+
+        // Overdue steps are clamped, and so are steps taken after the current time.
+        assert_eq!(previous.progress(&time(Duration::from_secs(5)), &steps(Some(1.0))), 1.0);
+        assert_eq!(previous.progress(&time(Duration::from_secs(1)), &steps(Some(1.0))), 0.0);
+    }
+
+    #[test]
+    fn progress_is_complete_without_an_interval() {
+        let previous = PreviousTime(Duration::from_secs(2));
+        let time = time(Duration::from_millis(2100));
 
-    if game_over.0 {
-        game_over.0 = false;
-        world.resource_mut::<NextState<GameState>>().set(GameState::Stopped);
+        assert_eq!(previous.progress(&time, &steps(None)), 1.0);
+        assert_eq!(previous.progress(&time, &steps(Some(0.0))), 1.0);
+        assert_eq!(previous.progress(&time, &steps(Some(-1.0))), 1.0);
     }
 }
@@ -0,0 +1,333 @@
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+
+use crate::{
+    arena::{self, Arena, Direction},
+    game::{self, GameOutcome, GameOver, GameRng},
+    profiling::SolverProfile,
+    settings::Settings,
+    snake::{self, Snake},
+    solver::Solver,
+};
+
+/// A single game running without a window, advanced one step at a time using the same systems as
+/// [`SolveStep`](crate::game::SolveStep).
+pub struct HeadlessGame {
+    world: World,
+    solve: Schedule,
+    manual: Schedule,
+    outcome: Option<GameOutcome>,
+}
+
+impl HeadlessGame {
+    /// Sets up a new game with the given `settings`, driven by `solver` when using
+    /// [`HeadlessGame::step`].
+    ///
+    /// If [`Settings::seed`] is [`None`] a random seed is used.
+    pub fn new(settings: Settings, solver: Solver) -> HeadlessGame {
+        let seed = settings.seed.unwrap_or_else(game::random_seed);
+        let mut world = World::new();
+
+        world.insert_resource(settings);
+        world.insert_resource(solver);
+        world.insert_resource(GameRng::new(seed));
+        world.init_resource::<Snake>();
+        world.init_resource::<GameOver>();
+        world.init_resource::<SolverProfile>();
+
+        world.run_system_once(arena::setup_arena).unwrap();
+        world.run_system_once(snake::setup_snake).unwrap();
+
+        let mut solve = Schedule::default();
+        solve.add_systems(
+            (
+                arena::spawn_food,
+                snake::compute_snake_direction,
+                arena::update_snake_position,
+                arena::check_win,
+                arena::check_stall,
+            )
+                .chain(),
+        );
+
+        let mut manual = Schedule::default();
+        manual.add_systems((arena::spawn_food, arena::update_snake_position, arena::check_win).chain());
+
+        let mut game = Self {
+            world,
+            solve,
+            manual,
+            outcome: None,
+        };
+
+        // Place the first food, so that it is visible before the first step.
+        game.world.run_system_once(arena::spawn_food).unwrap();
+
+        game
+    }
+
+    pub fn arena(&self) -> &Arena {
+        self.world.resource::<Arena>()
+    }
+
+    pub fn snake(&self) -> &Snake {
+        self.world.resource::<Snake>()
+    }
+
+    pub fn solver(&self) -> &Solver {
+        self.world.resource::<Solver>()
+    }
+
+    /// How the game ended, or [`None`] while it is still running.
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        self.outcome
+    }
+
+    /// Advances the game by one step in the direction chosen by the solver.
+    pub fn step(&mut self) -> Option<GameOutcome> {
+        if self.outcome.is_none() {
+            self.solve.run(&mut self.world);
+            self.outcome = self.world.resource_mut::<GameOver>().0.take();
+        }
+
+        self.outcome
+    }
+
+    /// Advances the game by one step after turning the snake to face `direction`, ignoring the
+    /// solver.
+    ///
+    /// Like a player steering by hand, the snake may take as long as it likes to reach the food, so
+    /// the game never ends as [`GameOutcome::Stalled`].
+    pub fn step_towards(&mut self, direction: Direction) -> Option<GameOutcome> {
+        if self.outcome.is_none() {
+            self.world.resource_mut::<Snake>().set_direction(direction);
+            self.manual.run(&mut self.world);
+            self.outcome = self.world.resource_mut::<GameOver>().0.take();
+        }
+
+        self.outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        arena::{Cell, Directions},
+        solver::{astar::AstarSolver, SolverRegistry},
+    };
+
+    const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+    fn settings(size: UVec2, seed: u64) -> Settings {
+        Settings {
+            arena_size: size,
+            seed: Some(seed),
+            ..Default::default()
+        }
+    }
+
+    /// Checks every invariant relating the arena, its adjacency graph and the snake.
+    fn check_invariants(game: &HeadlessGame) {
+        let arena = game.arena();
+        let snake = game.snake();
+
+        // The adjacency graph is exactly the set of cells the snake can move into, plus its head,
+        // with an edge between every pair of neighboring nodes.
+        for pos in arena.positions() {
+            let cell = arena.get_cell_unchecked(pos);
+            let free = matches!(cell, Cell::None | Cell::Food | Cell::SnakeHead);
+            assert_eq!(
+                arena.adjacencies.contains(pos),
+                free,
+                "node mismatch at {pos}\n{arena:?}"
+            );
+
+            if !free {
+                assert!(
+                    arena.adjacencies.get_directions(pos).is_empty(),
+                    "edges from body at {pos}"
+                );
+                continue;
+            }
+
+            for dir in DIRECTIONS {
+                let neighbor = pos.as_ivec2() + dir.offset();
+                let connected = arena.adjacencies.get_directions(pos).contains(Directions::from(dir));
+                let in_graph = neighbor.cmpge(IVec2::ZERO).all()
+                    && neighbor.cmplt(arena.size.as_ivec2()).all()
+                    && arena.adjacencies.contains(neighbor.as_uvec2());
+
+                assert_eq!(connected, in_graph, "edge mismatch at {pos} towards {dir:?}\n{arena:?}");
+
+                if in_graph {
+                    let back = arena.adjacencies.get_directions(neighbor.as_uvec2());
+                    assert!(back.contains(Directions::from(dir.flip())), "asymmetric edge at {pos}");
+                }
+            }
+        }
+
+        // The snake segments form a chain from the tail to the head.
+        assert_eq!(arena.body.front(), Some(&arena.head));
+        assert_eq!(arena.body.back(), Some(&arena.tail));
+
+        let mut pos = arena.tail;
+        let mut visited = 0;
+
+        while pos != arena.head {
+            let dir = arena
+                .adjacencies
+                .get_segment_direction(pos)
+                .unwrap_or_else(|| panic!("missing snake segment at {pos}\n{arena:?}"));
+            pos = pos + dir;
+            visited += 1;
+            assert!(visited < arena.body.len(), "snake segments form a loop\n{arena:?}");
+        }
+
+        assert_eq!(visited, arena.body.len() - 1);
+        assert_eq!(arena.adjacencies.snake_segments().count(), arena.body.len() - 1);
+
+        // The tail distances count up from the head, and the snake only falls short of its length
+        // on the step it ate.
+        let tails = arena.body.len() - 1;
+        if arena.just_ate {
+            assert_eq!(tails + 1, snake.length);
+        } else {
+            assert_eq!(tails, snake.length);
+        }
+
+        for (distance, &pos) in arena.body.iter().enumerate() {
+            let expected = if distance == 0 {
+                Cell::SnakeHead
+            } else {
+                Cell::SnakeTail { distance }
+            };
+
+            assert!(
+                arena.get_cell_unchecked(pos) == expected,
+                "wrong cell at {pos}\n{arena:?}"
+            );
+        }
+
+        let snake_cells = arena
+            .cells()
+            .filter(|(_, c)| matches!(c, Cell::SnakeHead | Cell::SnakeTail { .. }))
+            .count();
+        assert_eq!(snake_cells, arena.body.len());
+
+        // The food is where the arena says it is.
+        let food_cells = arena.cells().filter(|(_, c)| *c == Cell::Food).count();
+
+        match arena.food {
+            Some(food) => {
+                assert!(arena.get_cell_unchecked(food) == Cell::Food, "food missing at {food}");
+                assert_eq!(food_cells, 1);
+            },
+            None => assert_eq!(food_cells, 0),
+        }
+    }
+
+    #[test]
+    fn random_games_keep_invariants() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for seed in 0..200 {
+            let size = UVec2::new(rng.gen_range(4..12), rng.gen_range(3..12));
+            let mut game = HeadlessGame::new(settings(size, seed), Solver::new("astar", AstarSolver::default()));
+            check_invariants(&game);
+
+            for _ in 0..2000 {
+                let snake = game.snake();
+                let arena = game.arena();
+
+                // Prefer moves which don't end the game immediately, so that games get long.
+                let safe = DIRECTIONS
+                    .into_iter()
+                    .filter(|&dir| {
+                        snake.can_turn(dir) && arena.adjacencies.get_directions(arena.head).contains(dir.into())
+                    })
+                    .collect::<Vec<_>>();
+                let dir = if safe.is_empty() || rng.gen_bool(0.02) {
+                    *DIRECTIONS.choose(&mut rng).unwrap()
+                } else {
+                    *safe.choose(&mut rng).unwrap()
+                };
+
+                let outcome = game.step_towards(dir);
+                check_invariants(&game);
+
+                if outcome.is_some() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn losing_right_after_eating_keeps_just_ate() {
+        for seed in 0..20 {
+            let mut game = HeadlessGame::new(settings(UVec2::new(6, 6), seed), Solver::default());
+
+            while !game.arena().just_ate {
+                assert!(game.step().is_none());
+            }
+
+            let snake = game.snake();
+            let arena = game.arena();
+            let Some(&dir) = DIRECTIONS.iter().find(|&&dir| {
+                snake.can_turn(dir) && !arena.adjacencies.get_directions(arena.head).contains(dir.into())
+            }) else {
+                continue;
+            };
+
+            // The snake hasn't grown into its new length yet, which `just_ate` has to keep saying.
+            assert_eq!(game.step_towards(dir), Some(GameOutcome::Lost));
+            assert!(game.arena().just_ate);
+            check_invariants(&game);
+            return;
+        }
+
+        panic!("no game could run into something right after eating");
+    }
+
+    #[test]
+    fn manual_games_never_stall() {
+        let laps = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+        // Pick a game whose food is out of the way of the square the snake circles in.
+        let mut game = (0..)
+            .map(|seed| HeadlessGame::new(settings(UVec2::new(8, 8), seed), Solver::default()))
+            .find(|game| {
+                let arena = game.arena();
+                let square = [UVec2::ZERO, UVec2::Y, UVec2::ONE, UVec2::X].map(|offset| arena.head + offset);
+                !square.contains(&arena.food.unwrap())
+            })
+            .unwrap();
+
+        // Circling on the spot repeats the same states well past the budget of steps without food.
+        for dir in laps.into_iter().cycle().take(400) {
+            assert_eq!(game.step_towards(dir), None);
+        }
+
+        assert_eq!(game.snake().steps_since_food, 400);
+    }
+
+    #[test]
+    fn solver_games_keep_invariants() {
+        let registry = SolverRegistry::builtin();
+
+        for name in registry.names() {
+            for (size, seed) in [(UVec2::new(6, 6), 0), (UVec2::new(8, 6), 1), (UVec2::new(10, 10), 2)] {
+                let mut game = HeadlessGame::new(settings(size, seed), registry.create(name).unwrap());
+                check_invariants(&game);
+
+                while game.step().is_none() {
+                    check_invariants(&game);
+                }
+
+                check_invariants(&game);
+            }
+        }
+    }
+}
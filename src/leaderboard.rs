@@ -0,0 +1,395 @@
+use std::{
+    fmt,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use bevy::{prelude::*, utils::SystemTime};
+
+use crate::{
+    game::{GameFinished, GameMode, GameOutcome, GameRng},
+    settings::Settings,
+    solver::Solver,
+};
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_leaderboard)
+            .add_systems(Update, record_results.run_if(resource_exists::<Leaderboard>));
+    }
+}
+
+/// The best result for every combination of arena size, solver and game mode, persisted to disk.
+///
+/// In [`GameMode::Human`] the best result is the greatest length reached, while in
+/// [`GameMode::Computer`] it is the fewest steps taken to fill the entire arena.
+#[derive(Resource, Debug)]
+pub struct Leaderboard {
+    path: PathBuf,
+    entries: Vec<LeaderboardEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LeaderboardEntry {
+    pub arena_size: UVec2,
+    /// The name of the solver, or `human` in [`GameMode::Human`].
+    pub solver: String,
+    pub mode: GameMode,
+    /// The length reached in [`GameMode::Human`], or the number of steps taken in
+    /// [`GameMode::Computer`].
+    pub score: usize,
+    /// The date the result was achieved, as `YYYY-MM-DD`.
+    pub date: String,
+    pub seed: u64,
+}
+
+impl Leaderboard {
+    /// Loads the leaderboard stored at `path`, which is empty if the file doesn't exist yet.
+    pub fn load(path: impl Into<PathBuf>) -> io::Result<Leaderboard> {
+        let path = path.into();
+
+        let contents = match read(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+
+        let entries = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                LeaderboardEntry::parse(line).ok_or_else(|| {
+                    io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("{}: malformed entry on line {}", path.display(), i + 1),
+                    )
+                })
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(Self { path, entries })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let mut contents = String::new();
+
+        for entry in self.entries.iter() {
+            contents.push_str(&entry.to_line());
+            contents.push('\n');
+        }
+
+        write(&self.path, &contents)
+    }
+
+    pub fn entries(&self) -> &[LeaderboardEntry] {
+        &self.entries
+    }
+
+    /// Submits a result, replacing the current best result for the same arena size, solver and
+    /// mode if it is better.
+    ///
+    /// Returns whether the result was a new best.
+    pub fn submit(&mut self, entry: LeaderboardEntry) -> bool {
+        let existing = self
+            .entries
+            .iter_mut()
+            .find(|e| e.arena_size == entry.arena_size && e.solver == entry.solver && e.mode == entry.mode);
+
+        match existing {
+            Some(existing) => {
+                if entry.is_better_than(existing) {
+                    *existing = entry;
+                    true
+                } else {
+                    false
+                }
+            },
+            None => {
+                self.entries.push(entry);
+                self.entries.sort_by(|a, b| {
+                    (a.arena_size.x, a.arena_size.y, &a.solver).cmp(&(b.arena_size.x, b.arena_size.y, &b.solver))
+                });
+                true
+            },
+        }
+    }
+}
+
+impl LeaderboardEntry {
+    fn is_better_than(&self, other: &LeaderboardEntry) -> bool {
+        match self.mode {
+            GameMode::Human => self.score > other.score,
+            GameMode::Computer => self.score < other.score,
+        }
+    }
+
+    fn parse(line: &str) -> Option<LeaderboardEntry> {
+        let mut fields = line.split('\t');
+
+        let (width, height) = fields.next()?.split_once('x')?;
+        let arena_size = UVec2::new(width.parse().ok()?, height.parse().ok()?);
+        let solver = fields.next()?.to_string();
+        let mode = match fields.next()? {
+            "human" => GameMode::Human,
+            "computer" => GameMode::Computer,
+            _ => return None,
+        };
+        let score = fields.next()?.parse().ok()?;
+        let date = fields.next()?.to_string();
+        let seed = fields.next()?.parse().ok()?;
+
+        Some(Self {
+            arena_size,
+            solver,
+            mode,
+            score,
+            date,
+            seed,
+        })
+    }
+
+    fn to_line(&self) -> String {
+        format!(
+            "{}x{}\t{}\t{}\t{}\t{}\t{}",
+            self.arena_size.x,
+            self.arena_size.y,
+            self.solver,
+            mode_name(self.mode),
+            self.score,
+            self.date,
+            self.seed
+        )
+    }
+}
+
+impl fmt::Display for Leaderboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "arena     solver     mode        score date       seed")?;
+
+        for entry in self.entries.iter() {
+            writeln!(
+                f,
+                "{:<9} {:<10} {:<8} {:>8} {:<10} {}",
+                format!("{}x{}", entry.arena_size.x, entry.arena_size.y),
+                entry.solver,
+                mode_name(entry.mode),
+                entry.score,
+                entry.date,
+                entry.seed
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(path: &Path) -> io::Result<String> {
+    std::fs::read_to_string(path)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(path: &Path, contents: &str) -> io::Result<()> {
+    std::fs::write(path, contents)
+}
+
+/// There is no file system on the web, so the leaderboard always starts out empty there and is
+/// only kept for as long as the page is open.
+#[cfg(target_arch = "wasm32")]
+fn read(_path: &Path) -> io::Result<String> {
+    Err(ErrorKind::NotFound.into())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(_path: &Path, _contents: &str) -> io::Result<()> {
+    Ok(())
+}
+
+fn mode_name(mode: GameMode) -> &'static str {
+    match mode {
+        GameMode::Human => "human",
+        GameMode::Computer => "computer",
+    }
+}
+
+/// Formats the current date as `YYYY-MM-DD` in UTC.
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    date(secs)
+}
+
+/// Formats the date `secs` seconds after the Unix epoch as `YYYY-MM-DD` in UTC.
+fn date(secs: u64) -> String {
+    // Convert days since the epoch to a civil date in the proleptic Gregorian calendar.
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn load_leaderboard(mut commands: Commands, settings: Res<Settings>) {
+    match Leaderboard::load(&settings.leaderboard_path) {
+        Ok(leaderboard) => commands.insert_resource(leaderboard),
+        Err(e) => error!("failed to load leaderboard: {}", e),
+    }
+}
+
+fn record_results(
+    mut events: EventReader<GameFinished>,
+    mut leaderboard: ResMut<Leaderboard>,
+    settings: Res<Settings>,
+    mode: Res<State<GameMode>>,
+    solver: Option<Res<Solver>>,
+    rng: Res<GameRng>,
+) {
+    for event in events.read() {
+        let mode = *mode.get();
+
+        let (solver, score) = match mode {
+            GameMode::Human => ("human", event.length),
+            GameMode::Computer if event.outcome == GameOutcome::Won => {
+                let Some(solver) = &solver else { continue };
+                (solver.name(), event.steps)
+            },
+            GameMode::Computer => continue,
+        };
+
+        let entry = LeaderboardEntry {
+            arena_size: settings.arena_size,
+            solver: solver.to_string(),
+            mode,
+            score,
+            date: today(),
+            seed: rng.seed(),
+        };
+
+        if leaderboard.submit(entry) {
+            info!("new best result for {} on {}: {}", solver, settings.arena_size, score);
+
+            if let Err(e) = leaderboard.save() {
+                error!("failed to save leaderboard: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(mode: GameMode, score: usize) -> LeaderboardEntry {
+        LeaderboardEntry {
+            arena_size: UVec2::new(16, 12),
+            solver: String::from("hamilton"),
+            mode,
+            score,
+            date: String::from("2024-02-29"),
+            seed: 42,
+        }
+    }
+
+    fn leaderboard() -> Leaderboard {
+        Leaderboard {
+            path: PathBuf::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn entries_round_trip_through_lines() {
+        let entry = entry(GameMode::Computer, 1234);
+        let line = entry.to_line();
+        assert_eq!(line, "16x12\thamilton\tcomputer\t1234\t2024-02-29\t42");
+
+        let parsed = LeaderboardEntry::parse(&line).unwrap();
+        assert_eq!(parsed.arena_size, entry.arena_size);
+        assert_eq!(parsed.solver, entry.solver);
+        assert_eq!(parsed.mode, entry.mode);
+        assert_eq!(parsed.score, entry.score);
+        assert_eq!(parsed.date, entry.date);
+        assert_eq!(parsed.seed, entry.seed);
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected() {
+        for line in [
+            "",
+            "16x12\thamilton\tcomputer\t1234\t2024-02-29",
+            "16\thamilton\tcomputer\t1234\t2024-02-29\t42",
+            "16xa\thamilton\tcomputer\t1234\t2024-02-29\t42",
+            "16x12\thamilton\trobot\t1234\t2024-02-29\t42",
+            "16x12\thamilton\tcomputer\t-1\t2024-02-29\t42",
+            "16x12\thamilton\tcomputer\t1234\t2024-02-29\tseed",
+        ] {
+            assert!(LeaderboardEntry::parse(line).is_none(), "parsed {:?}", line);
+        }
+    }
+
+    #[test]
+    fn malformed_files_fail_to_load() {
+        let path = std::env::temp_dir().join(format!("snake-leaderboard-{}.tsv", std::process::id()));
+        std::fs::write(
+            &path,
+            format!("{}\n\nnot an entry\n", entry(GameMode::Human, 5).to_line()),
+        )
+        .unwrap();
+        let result = Leaderboard::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let error = result.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(error.to_string().ends_with("malformed entry on line 3"), "{}", error);
+    }
+
+    #[test]
+    fn humans_keep_their_longest_snake() {
+        let mut leaderboard = leaderboard();
+
+        assert!(leaderboard.submit(entry(GameMode::Human, 10)));
+        assert!(!leaderboard.submit(entry(GameMode::Human, 8)));
+        assert!(!leaderboard.submit(entry(GameMode::Human, 10)));
+        assert!(leaderboard.submit(entry(GameMode::Human, 12)));
+
+        assert_eq!(leaderboard.entries().len(), 1);
+        assert_eq!(leaderboard.entries()[0].score, 12);
+    }
+
+    #[test]
+    fn solvers_keep_their_fewest_steps() {
+        let mut leaderboard = leaderboard();
+
+        assert!(leaderboard.submit(entry(GameMode::Computer, 500)));
+        assert!(!leaderboard.submit(entry(GameMode::Computer, 600)));
+        assert!(leaderboard.submit(entry(GameMode::Computer, 400)));
+        // A different mode is a separate entry.
+        assert!(leaderboard.submit(entry(GameMode::Human, 3)));
+
+        assert_eq!(leaderboard.entries().len(), 2);
+        let computer = leaderboard.entries().iter().find(|e| e.mode == GameMode::Computer);
+        assert_eq!(computer.unwrap().score, 400);
+    }
+
+    #[test]
+    fn dates_are_formatted_in_utc() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(86_399), "1970-01-01");
+        assert_eq!(date(951_782_400), "2000-02-29");
+        assert_eq!(date(1_709_164_800), "2024-02-29");
+        assert_eq!(date(1_735_689_599), "2024-12-31");
+        assert_eq!(date(4_102_444_799), "2099-12-31");
+    }
+}
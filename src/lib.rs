@@ -0,0 +1,25 @@
+//! A snake game played by hand or by solvers, which can be extended with further solvers through
+//! [`AppExtSolvers::register_solver`](solver::AppExtSolvers::register_solver).
+
+pub mod adjacencies;
+pub mod arena;
+pub mod cell;
+pub mod debug;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod export;
+pub mod game;
+pub mod headless;
+pub mod leaderboard;
+pub mod profiling;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod protocol;
+pub mod settings;
+pub mod simulation;
+pub mod snake;
+pub mod solver;
+pub mod theme;
+pub mod tournament;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tui;
+pub mod ui;
+pub mod verify;
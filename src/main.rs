@@ -1,58 +1,436 @@
+use ::snake::{
+    arena, cell, debug, game, headless, leaderboard, profiling, settings, snake, solver, theme, tournament, ui, verify,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use ::snake::{export, tui};
 use bevy::prelude::*;
-
-mod adjacencies;
-mod arena;
-mod cell;
-mod debug;
-mod game;
-mod settings;
-mod snake;
-mod solver;
-mod ui;
+#[cfg(not(target_arch = "wasm32"))]
+use solver::AppExtSolvers;
 
 fn main() {
-    App::new()
-        .add_plugins((DefaultPlugins, game::SchedulesPlugin, ui::UiPlugin { inspector: true }))
-        .init_resource::<settings::Settings>()
-        .init_resource::<snake::Snake>()
-        .init_resource::<game::GameOver>()
-        .init_state::<game::GameState>()
-        .init_state::<game::GameMode>()
-        .register_type::<solver::Solver>()
-        .add_systems(Startup, (setup_camera, cell::setup_cells, arena::setup_arena))
-        .add_systems(PostStartup, (snake::setup_snake, settings::setup_time_steps))
-        .add_systems(
-            OnExit(game::GameState::Stopped),
-            (snake::setup_snake, arena::respawn_food),
-        )
-        .add_systems(OnEnter(game::GameMode::Computer), snake::setup_solver)
-        .add_systems(Update, (settings::update_time_steps,))
-        .add_systems(
-            game::SolveStep,
-            (
-                arena::spawn_food,
-                snake::update_snake_direction_human.run_if(in_state(game::GameMode::Human)),
-                snake::compute_snake_direction.run_if(in_state(game::GameMode::Computer)),
-                arena::update_snake_position,
-                arena::check_win,
-            )
-                .chain(),
-        )
-        .add_systems(
-            game::Draw,
-            (
-                arena::update_cell,
-                cell::update_cell_transform,
-                debug::debug_adjacencies,
-                // debug::debug_snake_segments,
-                debug::debug_solver_tables,
-                debug::debug_solver_paths.after(debug::debug_solver_tables),
-                debug::debug_solver_points,
-            ),
+    let mut args = std::env::args().skip(1);
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut bot = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut server = None;
+
+    match args.next().as_deref() {
+        None => (),
+        #[cfg(not(target_arch = "wasm32"))]
+        Some("bot") => {
+            let command = args.collect::<Vec<_>>();
+
+            if command.is_empty() {
+                eprintln!("usage: snake bot <command> [args]");
+                std::process::exit(2);
+            }
+
+            bot = Some(command);
+        },
+        #[cfg(not(target_arch = "wasm32"))]
+        Some("serve") => {
+            let address = args
+                .next()
+                .unwrap_or_else(|| solver::remote::RemoteSettings::default().address);
+            server = Some(address);
+        },
+        Some("leaderboard") => {
+            let path = settings::Settings::default().leaderboard_path;
+
+            match leaderboard::Leaderboard::load(&path) {
+                Ok(leaderboard) => print!("{}", leaderboard),
+                Err(e) => {
+                    eprintln!("failed to load leaderboard: {}", e);
+                    std::process::exit(1);
+                },
+            }
+
+            return;
+        },
+        Some("verify") => {
+            let max_size = args.next().map_or(Some(20), |s| s.parse().ok());
+            let seeds = args.next().map_or(Some(10), |s| s.parse().ok());
+
+            let (Some(max_size), Some(seeds)) = (max_size, seeds) else {
+                eprintln!("usage: snake verify [max size] [seeds]");
+                std::process::exit(2);
+            };
+
+            if !verify::run(max_size, seeds) {
+                std::process::exit(1);
+            }
+
+            return;
+        },
+        Some("solvers") => {
+            for name in solver::SolverRegistry::builtin().names() {
+                println!("{}", name);
+            }
+
+            return;
+        },
+        Some("tournament") => {
+            let args = args.collect::<Vec<_>>();
+
+            if let Err(e) = tournament_command(&args) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        },
+        #[cfg(not(target_arch = "wasm32"))]
+        Some("tui") => {
+            let args = args.collect::<Vec<_>>();
+
+            if let Err(e) = tui_command(&args) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        },
+        #[cfg(not(target_arch = "wasm32"))]
+        Some("export") => {
+            let args = args.collect::<Vec<_>>();
+
+            if let Err(e) = export_command(&args) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        },
+        Some("cycle") => {
+            let args = args.collect::<Vec<_>>();
+
+            if let Err(e) = cycle_command(&args) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+
+            return;
+        },
+        Some(command) => {
+            eprintln!("unknown command `{}`", command);
+            eprintln!("usage: snake [bot <command> [args] | serve [address] | tui ... | leaderboard | solvers | tournament ... | export ... | verify [max size] [seeds] | cycle (export | check) ...]");
+            std::process::exit(2);
+        },
+    }
+
+    let mut app = App::new();
+
+    app.add_plugins((
+        DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(primary_window()),
+            ..Default::default()
+        }),
+        game::SchedulesPlugin,
+        leaderboard::LeaderboardPlugin,
+        theme::ThemePlugin,
+        ui::UiPlugin {
+            inspector: cfg!(feature = "inspector"),
+        },
+        solver::SolversPlugin,
+    ))
+    .init_resource::<settings::Settings>()
+    .init_resource::<snake::Snake>()
+    .init_resource::<game::GameOver>()
+    .init_resource::<profiling::SolverProfile>()
+    .init_state::<game::GameState>()
+    .init_state::<game::GameMode>()
+    .add_systems(Startup, (setup_camera, cell::setup_cells, arena::setup_arena))
+    .add_systems(PostStartup, (snake::setup_snake, settings::setup_time_steps))
+    .add_systems(
+        OnExit(game::GameState::Stopped),
+        (snake::setup_snake.after(game::reseed_rng), arena::respawn_food),
+    )
+    .add_systems(OnEnter(game::GameMode::Computer), snake::setup_solver)
+    .add_systems(Update, (settings::update_time_steps,))
+    .add_systems(
+        game::SolveStep,
+        (
+            arena::spawn_food,
+            snake::update_snake_direction_human.run_if(in_state(game::GameMode::Human)),
+            snake::compute_snake_direction.run_if(in_state(game::GameMode::Computer)),
+            arena::update_snake_position,
+            arena::check_win,
+            arena::check_stall.run_if(in_state(game::GameMode::Computer)),
         )
-        .run();
+            .chain(),
+    )
+    .add_systems(
+        game::Draw,
+        (
+            arena::update_cell,
+            cell::update_cell_transform,
+            cell::interpolate_snake
+                .after(cell::update_cell_transform)
+                .after(arena::update_cell),
+            debug::debug_adjacencies,
+            // debug::debug_snake_segments,
+            debug::debug_solver_tables,
+            debug::debug_solver_paths.after(debug::debug_solver_tables),
+            debug::debug_solver_points,
+            debug::debug_analysis,
+        ),
+    );
+
+    // Play with a bot given on the command line, as long as no other solver is picked.
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(command) = bot {
+        app.register_solver_with("external", move || {
+            solver::external::ExternalSolver::new(command.clone())
+        });
+        app.world_mut().resource_mut::<ui::Configuration>().solver = "external";
+    }
+
+    // Let bots connect over TCP, as long as no other solver is picked.
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(address) = server {
+        app.register_solver_with("remote", move || solver::remote::RemoteSolver::new(address.clone()));
+        app.world_mut().resource_mut::<ui::Configuration>().solver = "remote";
+    }
+
+    app.run();
+}
+
+/// On the web, the game draws into the `#bevy` canvas of the page and resizes along with it.
+fn primary_window() -> Window {
+    Window {
+        #[cfg(target_arch = "wasm32")]
+        canvas: Some(String::from("#bevy")),
+        #[cfg(target_arch = "wasm32")]
+        fit_canvas_to_parent: true,
+        ..Default::default()
+    }
 }
 
 fn setup_camera(mut commands: Commands) {
     commands.spawn(Camera2d);
 }
+
+/// Runs `snake cycle export <width>x<height> [seed] [file]`, which writes the cycle the Hamilton
+/// solver would start a game with to `file` or prints it, or `snake cycle check <file>`, which
+/// validates a cycle file.
+fn cycle_command(args: &[String]) -> Result<(), String> {
+    const USAGE: &str = "usage: snake cycle (export <width>x<height> [seed] [file] | check <file>)";
+
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    match args[..] {
+        ["export", size, ref rest @ ..] if rest.len() <= 2 => {
+            let size = parse_size(size).ok_or(USAGE)??;
+            check_cycle("hamilton", size)?;
+            let seed = rest.first().map_or(Some(0), |s| s.parse().ok()).ok_or(USAGE)?;
+
+            let settings = settings::Settings {
+                arena_size: size,
+                seed: Some(seed),
+                ..Default::default()
+            };
+            let solver = solver::Solver::new("hamilton", solver::hamilton::HamiltonSolver::default());
+            let game = headless::HeadlessGame::new(settings, solver);
+
+            let Some(hamilton) = game.solver().downcast_ref::<solver::hamilton::HamiltonSolver>() else {
+                unreachable!()
+            };
+
+            match rest.get(1) {
+                Some(path) => hamilton
+                    .save_cycle(path)
+                    .map_err(|e| format!("failed to save cycle: {}", e)),
+                None => {
+                    print!("{}", solver::cycle::to_arrows(size, hamilton.cycle()));
+                    Ok(())
+                },
+            }
+        },
+        ["check", path] => {
+            let (size, _) = solver::cycle::load(path).map_err(|e| e.to_string())?;
+            println!("{}: valid cycle for a {}x{} arena", path, size.x, size.y);
+
+            Ok(())
+        },
+        _ => Err(String::from(USAGE)),
+    }
+}
+
+/// Runs `snake tui [human | <solver>] [<width>x<height>] [--interval <seconds>]`, which plays in
+/// the terminal, steered with the arrow keys or by the given solver.
+#[cfg(not(target_arch = "wasm32"))]
+fn tui_command(args: &[String]) -> Result<(), String> {
+    const USAGE: &str = "usage: snake tui [human | <solver>] [<width>x<height>] [--interval <seconds>]";
+
+    let registry = solver::SolverRegistry::builtin();
+    let mut player = None;
+    let mut size = None;
+    let mut interval = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--interval" => {
+                let seconds = args.next().and_then(|s| s.parse::<f32>().ok()).ok_or(USAGE)?;
+                interval = Some(std::time::Duration::try_from_secs_f32(seconds).map_err(|_| USAGE)?);
+            },
+            _ => match parse_size(arg) {
+                Some(parsed) if size.is_none() => size = Some(parsed?),
+                None if player.is_none() => player = Some(arg.as_str()),
+                _ => return Err(String::from(USAGE)),
+            },
+        }
+    }
+
+    let solver = match player {
+        None | Some("human") => None,
+        Some(name) if registry.create(name).is_some() => Some(name),
+        Some(name) => return Err(format!("unknown solver `{}`", name)),
+    };
+    let size = size.unwrap_or(UVec2::splat(16));
+
+    if let Some(name) = solver {
+        check_cycle(name, size)?;
+    }
+
+    tui::run(
+        &registry,
+        solver,
+        size,
+        interval.unwrap_or(std::time::Duration::from_millis(100)),
+    )
+    .map_err(|e| format!("terminal error: {}", e))
+}
+
+/// Runs `snake export <solver> <width>x<height> <file.gif | directory> [--seed <seed>] [--cell-size
+/// <pixels>] [--interval <seconds>]`, which plays a game and writes it as an animated GIF or as
+/// numbered PNG frames.
+#[cfg(not(target_arch = "wasm32"))]
+fn export_command(args: &[String]) -> Result<(), String> {
+    const USAGE: &str = "usage: snake export <solver> <width>x<height> <file.gif | directory> [--seed <seed>] [--cell-size <pixels>] [--interval <seconds>]";
+
+    let registry = solver::SolverRegistry::builtin();
+    let mut positional = Vec::new();
+    let mut seed = 0;
+    let mut cell_size = 16;
+    let mut interval = 0.1;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => seed = args.next().and_then(|s| s.parse().ok()).ok_or(USAGE)?,
+            "--cell-size" => cell_size = args.next().and_then(|s| s.parse().ok()).ok_or(USAGE)?,
+            "--interval" => interval = args.next().and_then(|s| s.parse().ok()).ok_or(USAGE)?,
+            _ => positional.push(arg.as_str()),
+        }
+    }
+
+    let [name, size, path] = positional[..] else {
+        return Err(String::from(USAGE));
+    };
+    let size = parse_size(size).ok_or(USAGE)??;
+    let solver = registry
+        .create(name)
+        .ok_or_else(|| format!("unknown solver `{}`", name))?;
+    check_cycle(name, size)?;
+    let interval = std::time::Duration::try_from_secs_f32(interval).map_err(|_| USAGE)?;
+
+    let settings = settings::Settings {
+        arena_size: size,
+        seed: Some(seed),
+        ..Default::default()
+    };
+    let mut game = headless::HeadlessGame::new(settings, solver);
+    let mut frames =
+        export::Frames::create(path.as_ref(), interval).map_err(|e| format!("failed to create {}: {}", path, e))?;
+
+    let count = export::export(&mut game, &mut frames, &settings::ColorSettings::default(), cell_size)
+        .map_err(|e| format!("failed to write {}: {}", path, e))?;
+    println!("wrote {} frames to {}", count, path);
+
+    Ok(())
+}
+
+/// Runs `snake tournament <width>x<height> [seeds] [--solvers <name>,...] [--csv <file>]`, which
+/// plays a round-robin tournament between the given solvers, or every built-in solver, prints
+/// their ratings and optionally writes every game result to a CSV file.
+fn tournament_command(args: &[String]) -> Result<(), String> {
+    const USAGE: &str = "usage: snake tournament <width>x<height> [seeds] [--solvers <name>,...] [--csv <file>]";
+
+    let registry = solver::SolverRegistry::builtin();
+    let mut size = None;
+    let mut seeds = None;
+    let mut solvers = None;
+    let mut csv = None;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--solvers" => solvers = Some(args.next().ok_or(USAGE)?.split(',').collect::<Vec<_>>()),
+            "--csv" => csv = Some(args.next().ok_or(USAGE)?),
+            _ if size.is_none() => size = Some(parse_size(arg).ok_or(USAGE)??),
+            _ if seeds.is_none() => seeds = Some(arg.parse::<u64>().map_err(|_| USAGE)?),
+            _ => return Err(String::from(USAGE)),
+        }
+    }
+
+    let size = size.ok_or(USAGE)?;
+    let seeds = seeds.unwrap_or(10);
+    let tournament = match solvers {
+        Some(solvers) => {
+            for name in &solvers {
+                check_cycle(name, size)?;
+            }
+
+            tournament::Tournament::run(&registry, &solvers, size, seeds)?
+        },
+        None => {
+            if let Err(e) = check_cycle("hamilton", size) {
+                eprintln!("leaving out hamilton: {}", e);
+            }
+
+            tournament::Tournament::run_all(&registry, size, seeds)
+        },
+    };
+
+    print!("{}", tournament);
+
+    if let Some(path) = csv {
+        let file = std::fs::File::create(path).map_err(|e| format!("failed to create {}: {}", path, e))?;
+        tournament
+            .write_csv(std::io::BufWriter::new(file))
+            .map_err(|e| format!("failed to write {}: {}", path, e))?;
+    }
+
+    Ok(())
+}
+
+/// Parses an arena size given as `<width>x<height>`, returning [`None`] if `arg` isn't one, and an
+/// error if the arena is smaller than [`settings::MIN_ARENA_SIZE`].
+fn parse_size(arg: &str) -> Option<Result<UVec2, String>> {
+    let (width, height) = arg.split_once('x')?;
+    let size = UVec2::new(width.parse().ok()?, height.parse().ok()?);
+    let min = settings::MIN_ARENA_SIZE;
+
+    if size.cmplt(min).any() {
+        return Some(Err(format!(
+            "a {}x{} arena is too small, it must be at least {}x{}",
+            size.x, size.y, min.x, min.y
+        )));
+    }
+
+    Some(Ok(size))
+}
+
+/// Fails if `solver` follows a Hamiltonian cycle, but an arena of the given `size` has none through
+/// the snake, as told by [`HamiltonSolver::supports`](solver::hamilton::HamiltonSolver::supports).
+fn check_cycle(solver: &str, size: UVec2) -> Result<(), String> {
+    if solver == "hamilton" && !solver::hamilton::HamiltonSolver::supports(size) {
+        return Err(format!(
+            "a {}x{} arena has no Hamiltonian cycle through the snake for the {} solver to follow",
+            size.x, size.y, solver
+        ));
+    }
+
+    Ok(())
+}
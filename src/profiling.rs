@@ -0,0 +1,215 @@
+use std::{cell::Cell, fmt};
+
+use bevy::{prelude::*, utils::Instant};
+
+thread_local! {
+    static ASTAR_EXPANSIONS: Cell<u64> = const { Cell::new(0) };
+    static LONGEST_PATH_EXTENSIONS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Counts a node expanded by the A* search in the solver pathfinding.
+#[inline]
+pub fn count_astar_expansion() {
+    ASTAR_EXPANSIONS.with(|c| c.set(c.get() + 1));
+}
+
+/// Counts a detour inserted into a path while computing a longest path.
+#[inline]
+pub fn count_longest_path_extension() {
+    LONGEST_PATH_EXTENSIONS.with(|c| c.set(c.get() + 1));
+}
+
+/// The cost of a single decision made by a solver.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StepProfile {
+    pub micros: u64,
+    pub astar_expansions: u64,
+    pub longest_path_extensions: u64,
+}
+
+/// Runs `f`, measuring how long it takes and how much pathfinding work it does.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, StepProfile) {
+    ASTAR_EXPANSIONS.with(|c| c.set(0));
+    LONGEST_PATH_EXTENSIONS.with(|c| c.set(0));
+
+    let start = Instant::now();
+    let value = f();
+    let micros = start.elapsed().as_micros() as u64;
+
+    let profile = StepProfile {
+        micros,
+        astar_expansions: ASTAR_EXPANSIONS.with(|c| c.get()),
+        longest_path_extensions: LONGEST_PATH_EXTENSIONS.with(|c| c.get()),
+    };
+
+    (value, profile)
+}
+
+/// Histograms of the cost of every decision made by the current solver.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct SolverProfile {
+    /// Time taken per decision, in microseconds.
+    pub decision_micros: Histogram,
+    pub astar_expansions: Histogram,
+    pub longest_path_extensions: Histogram,
+}
+
+impl SolverProfile {
+    pub fn record(&mut self, step: StepProfile) {
+        self.decision_micros.record(step.micros);
+        self.astar_expansions.record(step.astar_expansions);
+        self.longest_path_extensions.record(step.longest_path_extensions);
+    }
+}
+
+/// A histogram with power-of-two buckets, where bucket `0` holds zero and bucket `i` holds the
+/// values in `2^(i - 1)..2^i`.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    buckets: [u64; 65],
+    count: u64,
+    sum: u64,
+    max: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: [0; 65],
+            count: 0,
+            sum: 0,
+            max: 0,
+        }
+    }
+}
+
+impl Histogram {
+    pub fn record(&mut self, value: u64) {
+        let bucket = (u64::BITS - value.leading_zeros()) as usize;
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.sum = self.sum.saturating_add(value);
+        self.max = self.max.max(value);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum as f64 / self.count as f64
+        }
+    }
+
+    /// An upper bound of the value below which the fraction `q` of recorded values fall.
+    pub fn quantile(&self, q: f64) -> u64 {
+        let target = (q.clamp(0.0, 1.0) * self.count as f64).ceil() as u64;
+        let mut seen = 0;
+
+        for (i, &n) in self.buckets.iter().enumerate() {
+            seen += n;
+
+            if seen >= target.max(1) {
+                let upper = if i == 0 { 0 } else { (1u128 << i) - 1 };
+                return (upper as u64).min(self.max);
+            }
+        }
+
+        self.max
+    }
+
+    /// The non-empty buckets as `(lower bound, upper bound, count)`.
+    pub fn buckets(&self) -> impl Iterator<Item = (u64, u64, u64)> + '_ {
+        self.buckets.iter().enumerate().filter(|(_, &n)| n > 0).map(|(i, &n)| {
+            if i == 0 {
+                (0, 0, n)
+            } else {
+                (1 << (i - 1), ((1u128 << i) - 1) as u64, n)
+            }
+        })
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "n={} mean={:.1} p50<={} p90<={} p99<={} max={}",
+            self.count,
+            self.mean(),
+            self.quantile(0.5),
+            self.quantile(0.9),
+            self.quantile(0.99),
+            self.max
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn histogram(values: impl IntoIterator<Item = u64>) -> Histogram {
+        let mut histogram = Histogram::default();
+        for value in values {
+            histogram.record(value);
+        }
+        histogram
+    }
+
+    #[test]
+    fn values_fall_into_power_of_two_buckets() {
+        let histogram = histogram([0, 1, 2, 3, 4, 7, 8]);
+
+        assert_eq!(
+            histogram.buckets().collect::<Vec<_>>(),
+            [(0, 0, 1), (1, 1, 1), (2, 3, 2), (4, 7, 2), (8, 15, 1)]
+        );
+        assert_eq!(histogram.count(), 7);
+        assert_eq!(histogram.max(), 8);
+        assert_eq!(histogram.mean(), 25.0 / 7.0);
+    }
+
+    #[test]
+    fn extreme_values_have_their_own_buckets() {
+        let histogram = histogram([0, u64::MAX, u64::MAX]);
+
+        assert_eq!(
+            histogram.buckets().collect::<Vec<_>>(),
+            [(0, 0, 1), (1 << 63, u64::MAX, 2)]
+        );
+        assert_eq!(histogram.max(), u64::MAX);
+        assert_eq!(histogram.quantile(0.0), 0);
+        assert_eq!(histogram.quantile(0.5), u64::MAX);
+        assert_eq!(histogram.quantile(1.0), u64::MAX);
+    }
+
+    #[test]
+    fn quantiles_are_bucket_upper_bounds() {
+        let histogram = histogram([1, 2, 3, 5, 100]);
+
+        assert_eq!(histogram.quantile(0.2), 1);
+        assert_eq!(histogram.quantile(0.5), 3);
+        assert_eq!(histogram.quantile(0.8), 7);
+        // The last bucket reaches up to 127, but nothing above the maximum was recorded.
+        assert_eq!(histogram.quantile(1.0), 100);
+        assert_eq!(histogram.quantile(2.0), 100);
+    }
+
+    #[test]
+    fn empty_histograms_report_zero() {
+        let histogram = Histogram::default();
+
+        assert_eq!(histogram.quantile(0.5), 0);
+        assert_eq!(histogram.quantile(1.0), 0);
+        assert_eq!(histogram.mean(), 0.0);
+        assert_eq!(histogram.buckets().count(), 0);
+    }
+}
@@ -0,0 +1,200 @@
+//! Line-based JSON protocol for bots playing the game from outside this process.
+//!
+//! Every message is a single line of JSON. The game sends a [`Message`] to the bot, and after each
+//! [`Message::State`] the bot answers with a [`Reply`] naming the direction to move in and echoing
+//! the `steps` of the state, such as `{"direction":"left","steps":12}`. Replies to any other step
+//! are thrown away.
+//!
+//! Positions are `[x, y]` pairs, with `[0, 0]` at the bottom left of the arena and `y` pointing up.
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::math::UVec2;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    arena::{Arena, Direction},
+    snake::Snake,
+};
+
+/// A message sent from the game to a bot.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Message {
+    /// Sent once at the start of every game.
+    Start { width: u32, height: u32, seed: u64 },
+    /// Sent before every move, which the bot must reply to.
+    State {
+        width: u32,
+        height: u32,
+        /// The segments of the snake, from its head to its tail.
+        body: Vec<[u32; 2]>,
+        food: Option<[u32; 2]>,
+        /// The direction the snake last moved in. The snake can't turn back into the opposite
+        /// direction.
+        direction: Direction,
+        length: usize,
+        steps: usize,
+    },
+}
+
+impl Message {
+    pub fn start(arena: &Arena, seed: u64) -> Message {
+        Message::Start {
+            width: arena.size.x,
+            height: arena.size.y,
+            seed,
+        }
+    }
+
+    pub fn state(snake: &Snake, arena: &Arena) -> Message {
+        Message::State {
+            width: arena.size.x,
+            height: arena.size.y,
+            body: arena.body.iter().map(|&pos| point(pos)).collect(),
+            food: arena.food.map(point),
+            direction: snake.direction,
+            length: snake.length,
+            steps: snake.steps,
+        }
+    }
+}
+
+/// The answer of a bot to a [`Message::State`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Reply {
+    pub direction: Direction,
+    /// The `steps` of the state this replies to.
+    pub steps: usize,
+}
+
+fn point(pos: UVec2) -> [u32; 2] {
+    [pos.x, pos.y]
+}
+
+/// Writes `value` as a single line of JSON and flushes `writer`.
+pub fn send(mut writer: impl Write, value: &impl Serialize) -> io::Result<()> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()
+}
+
+/// Reads a single line of JSON from `reader`, returning [`None`] at the end of the input.
+pub fn receive<T: for<'de> Deserialize<'de>>(mut reader: impl BufRead) -> io::Result<Option<T>> {
+    let mut line = String::new();
+
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(serde_json::from_str(&line)?))
+}
+
+/// The game's end of a connection to a bot.
+pub struct Connection {
+    writer: Box<dyn Write + Send + Sync>,
+    replies: Mutex<Receiver<io::Result<Reply>>>,
+}
+
+impl Connection {
+    /// Talks to a bot which reads messages from `writer` and writes replies to `reader`.
+    pub fn new(reader: impl Read + Send + 'static, writer: impl Write + Send + Sync + 'static) -> Connection {
+        let mut reader = BufReader::new(reader);
+        let (sender, receiver) = mpsc::channel();
+
+        // Replies are read on a separate thread, so that waiting for them can time out.
+        thread::spawn(move || loop {
+            let reply = match receive(&mut reader) {
+                Ok(Some(reply)) => Ok(reply),
+                Ok(None) => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "bot disconnected")),
+                Err(e) => Err(e),
+            };
+            let stop = reply.is_err();
+
+            if sender.send(reply).is_err() || stop {
+                break;
+            }
+        });
+
+        Self {
+            writer: Box::new(writer),
+            replies: Mutex::new(receiver),
+        }
+    }
+
+    pub fn send(&mut self, message: &Message) -> io::Result<()> {
+        send(&mut self.writer, message)
+    }
+
+    /// Sends `message` and waits up to `timeout` for the reply, which is [`None`] if it doesn't
+    /// arrive in time.
+    ///
+    /// Late replies to earlier states are thrown away, so that they aren't taken for the reply to
+    /// this one.
+    pub fn request(&mut self, message: &Message, timeout: Duration) -> io::Result<Option<Reply>> {
+        let steps = match message {
+            Message::State { steps, .. } => Some(*steps),
+            Message::Start { .. } => None,
+        };
+        let replies = self.replies.get_mut().unwrap();
+        let deadline = Instant::now() + timeout;
+
+        send(&mut self.writer, message)?;
+
+        loop {
+            match replies.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(Ok(reply)) if steps.is_none_or(|steps| steps == reply.steps) => return Ok(Some(reply)),
+                Ok(Ok(_)) => (),
+                Ok(Err(e)) => return Err(e),
+                Err(RecvTimeoutError::Timeout) => return Ok(None),
+                Err(RecvTimeoutError::Disconnected) => return Err(io::ErrorKind::BrokenPipe.into()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn late_replies_are_thrown_away() {
+        let replies = [
+            Reply {
+                direction: Direction::Up,
+                steps: 4,
+            },
+            Reply {
+                direction: Direction::Left,
+                steps: 5,
+            },
+        ];
+        let mut input = Vec::new();
+        for reply in &replies {
+            send(&mut input, reply).unwrap();
+        }
+
+        let mut connection = Connection::new(io::Cursor::new(input), io::sink());
+        let state = Message::State {
+            width: 4,
+            height: 4,
+            body: vec![[1, 1]],
+            food: None,
+            direction: Direction::Up,
+            length: 1,
+            steps: 5,
+        };
+
+        let reply = connection.request(&state, Duration::from_secs(5)).unwrap();
+        assert_eq!(reply, Some(replies[1]));
+    }
+}
@@ -1,14 +1,42 @@
-use std::num::{NonZero, NonZeroU32};
+use std::{
+    num::{NonZero, NonZeroU32},
+    path::PathBuf,
+};
 
 use bevy::prelude::*;
 
 use crate::game::TimeSteps;
 
+/// The smallest arena the snake fits into as it starts out, three cells long along the middle row,
+/// with another row to turn into.
+pub const MIN_ARENA_SIZE: UVec2 = UVec2::new(3, 2);
+
 #[derive(Resource)]
 pub struct Settings {
     pub arena_size: UVec2,
     pub interval: Option<f32>,
     pub substeps: NonZeroU32,
+    /// Move the head and tail of the snake smoothly between cells over the [`interval`], rather
+    /// than jumping from cell to cell on every step. Only the flat theme is interpolated.
+    ///
+    /// [`interval`]: Settings::interval
+    pub interpolate: bool,
+    /// The maximum number of steps the snake may take without eating before the game is
+    /// considered stalled.
+    ///
+    /// [`None`] uses the area of the arena, which is enough to reach food from anywhere.
+    pub max_steps_without_food: Option<usize>,
+    /// Seed used for every game.
+    ///
+    /// [`None`] picks a new random seed at the start of each game.
+    pub seed: Option<u64>,
+    /// File in which the best results are persisted.
+    pub leaderboard_path: PathBuf,
+    /// Directory of texture atlases to offer as themes, see [`theme`](crate::theme).
+    pub themes_path: PathBuf,
+    /// File the cycle of the running Hamilton solver is saved to with the C key, as written by
+    /// [`HamiltonSolver::save_cycle`](crate::solver::hamilton::HamiltonSolver::save_cycle).
+    pub cycle_path: PathBuf,
     pub colors: ColorSettings,
 }
 
@@ -19,6 +47,12 @@ impl Default for Settings {
             interval: None,
             // interval: Some(0.1),
             substeps: NonZero::new(1).unwrap(),
+            interpolate: false,
+            max_steps_without_food: None,
+            seed: None,
+            leaderboard_path: PathBuf::from("leaderboard.tsv"),
+            themes_path: PathBuf::from("themes"),
+            cycle_path: PathBuf::from("cycle.txt"),
             colors: ColorSettings::default(),
         }
     }
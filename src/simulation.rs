@@ -0,0 +1,190 @@
+use std::collections::VecDeque;
+
+use bevy::math::UVec2;
+
+use crate::{
+    adjacencies::AdjacencyGraph,
+    arena::{Arena, Direction},
+    snake::Snake,
+};
+
+/// A copy of the state of a game which solvers can move a virtual snake around in, following the
+/// same rules as [`update_snake_position`](crate::arena::update_snake_position).
+///
+/// Only the adjacency graph and the body of the snake are copied, so cloning a simulation to try
+/// out several moves is cheap. Food eaten in a simulation isn't replaced.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    adjacencies: AdjacencyGraph,
+    body: VecDeque<UVec2>,
+    behind: UVec2,
+    food: Option<UVec2>,
+    length: usize,
+    just_ate: bool,
+}
+
+impl Simulation {
+    pub fn new(snake: &Snake, arena: &Arena) -> Simulation {
+        Self {
+            adjacencies: arena.adjacencies.clone(),
+            body: arena.body.clone(),
+            behind: arena.behind,
+            food: arena.food,
+            length: snake.length,
+            just_ate: arena.just_ate,
+        }
+    }
+
+    /// Moves the snake one cell in `direction`, returning whether it ate the food.
+    ///
+    /// Returns [`None`] and leaves the simulation unchanged if the snake would run into a wall or
+    /// itself.
+    pub fn step(&mut self, direction: Direction) -> Option<bool> {
+        let head = self.head();
+
+        if !self.adjacencies.get_directions(head).contains(direction.into()) {
+            return None;
+        }
+
+        let next = head + direction;
+        let remove = self.body.len() > self.length;
+
+        self.just_ate = false;
+        self.adjacencies.remove(head);
+        self.adjacencies.insert_snake_segment(head, direction);
+
+        if self.food == Some(next) {
+            self.length += 1;
+            self.food = None;
+            self.just_ate = true;
+        }
+
+        self.body.push_front(next);
+
+        if remove {
+            let pos = self.body.pop_back().unwrap();
+            self.adjacencies.insert(pos);
+            self.adjacencies.remove_snake_segment(pos);
+            self.behind = pos;
+        }
+
+        Some(self.just_ate)
+    }
+
+    /// Moves the snake along `path`, stopping early if it runs into anything.
+    ///
+    /// Returns whether the whole path was followed.
+    pub fn follow(&mut self, path: &[Direction]) -> bool {
+        path.iter().all(|&direction| self.step(direction).is_some())
+    }
+
+    pub fn adjacencies(&self) -> &AdjacencyGraph {
+        &self.adjacencies
+    }
+
+    pub fn head(&self) -> UVec2 {
+        self.body[0]
+    }
+
+    pub fn tail(&self) -> UVec2 {
+        self.body[self.body.len() - 1]
+    }
+
+    /// The cell the tail most recently left.
+    pub fn behind(&self) -> UVec2 {
+        self.behind
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        headless::HeadlessGame,
+        settings::Settings,
+        solver::{astar::AstarSolver, Solver},
+    };
+
+    const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+    #[test]
+    fn simulation_matches_game() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for seed in 0..100 {
+            let size = UVec2::new(rng.gen_range(4..10), rng.gen_range(3..10));
+            let settings = Settings {
+                arena_size: size,
+                seed: Some(seed),
+                ..Default::default()
+            };
+            let mut game = HeadlessGame::new(settings, Solver::new("astar", AstarSolver::default()));
+
+            for _ in 0..1000 {
+                // Food is only placed at the start of the next step after it was eaten, which the
+                // simulation can't know about.
+                if game.arena().food.is_none() {
+                    if game.step().is_some() {
+                        break;
+                    }
+
+                    continue;
+                }
+
+                let mut simulation = Simulation::new(game.snake(), game.arena());
+
+                // Mostly take legal moves, but sometimes run into things.
+                let arena = game.arena();
+                let legal = DIRECTIONS
+                    .into_iter()
+                    .filter(|&dir| arena.adjacencies.get_directions(arena.head).contains(dir.into()))
+                    .collect::<Vec<_>>();
+                let dir = if legal.is_empty() || rng.gen_bool(0.02) {
+                    *DIRECTIONS.choose(&mut rng).unwrap()
+                } else {
+                    *legal.choose(&mut rng).unwrap()
+                };
+
+                let simulated = simulation.step(dir);
+                let outcome = game.step_towards(dir);
+                let arena = game.arena();
+
+                if simulated.is_none() {
+                    assert!(outcome.is_some(), "simulation ran into something the game didn't");
+                    break;
+                }
+
+                assert_eq!(simulated, Some(arena.just_ate));
+                assert_eq!(simulation.body, arena.body);
+                assert_eq!(simulation.head(), arena.head);
+                assert_eq!(simulation.tail(), arena.tail);
+                assert_eq!(simulation.behind(), arena.behind);
+                assert_eq!(simulation.length(), game.snake().length);
+
+                for y in 0..size.y {
+                    for x in 0..size.x {
+                        let pos = UVec2::new(x, y);
+                        assert_eq!(
+                            simulation.adjacencies().get_directions(pos).bits(),
+                            arena.adjacencies.get_directions(pos).bits()
+                        );
+                        assert_eq!(
+                            simulation.adjacencies().get_segment_direction(pos),
+                            arena.adjacencies.get_segment_direction(pos)
+                        );
+                    }
+                }
+
+                if outcome.is_some() {
+                    break;
+                }
+            }
+        }
+    }
+}
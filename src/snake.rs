@@ -1,15 +1,23 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
 use crate::{
     arena::{Arena, Cell, Direction, Directions},
-    game::LastSolverInput,
-    solver::Solver,
+    game::{GameRng, LastSolverInput, TimeSteps},
+    profiling::{self, SolverProfile},
+    solver::{Solver, SolverRegistry},
+    ui::Configuration,
 };
 
 #[derive(Resource)]
 pub struct Snake {
     pub direction: Direction,
     pub length: usize,
+    /// The number of steps taken since the start of the game.
+    pub steps: usize,
+    /// The number of steps taken since the snake last ate.
+    pub steps_since_food: usize,
     possible_directions: Directions,
 }
 
@@ -18,12 +26,28 @@ impl Default for Snake {
         Self {
             direction: Direction::Left,
             length: 2,
+            steps: 0,
+            steps_since_food: 0,
             possible_directions: !Directions::RIGHT,
         }
     }
 }
 
-pub fn setup_snake(mut commands: Commands, mut arena: ResMut<Arena>, mut solver: ResMut<Solver>) {
+impl Snake {
+    /// Whether the snake can turn to face `direction`, which is any direction except back into
+    /// itself.
+    pub fn can_turn(&self, direction: Direction) -> bool {
+        self.possible_directions.contains(direction.into())
+    }
+
+    /// Turns the snake to face `direction` for its next step.
+    pub fn set_direction(&mut self, direction: Direction) {
+        self.possible_directions = !Directions::from(direction.flip());
+        self.direction = direction;
+    }
+}
+
+pub fn setup_snake(mut commands: Commands, mut arena: ResMut<Arena>, mut solver: ResMut<Solver>, rng: Res<GameRng>) {
     arena.adjacencies.reset();
     let head = arena.size / 2 - UVec2::new(1, 0);
     let mid = head + UVec2::new(1, 0);
@@ -32,31 +56,31 @@ pub fn setup_snake(mut commands: Commands, mut arena: ResMut<Arena>, mut solver:
     arena.tail = tail;
     arena.behind = tail + UVec2::new(1, 0);
 
-    for pos in arena.positions() {
-        let cell = arena.get_cell_unchecked_mut(pos);
+    arena.body = [head, mid, tail].into();
 
+    for pos in arena.positions() {
         if pos == head {
-            *cell = Cell::SnakeHead;
+            arena.set_cell_unchecked(pos, Cell::SnakeHead);
             arena.adjacencies.insert(pos);
         } else if pos == mid {
-            *cell = Cell::SnakeTail { distance: 1 };
+            arena.set_cell_unchecked(pos, Cell::SnakeTail { distance: 1 });
             arena.adjacencies.insert_snake_segment(pos, Direction::Left);
         } else if pos == tail {
-            *cell = Cell::SnakeTail { distance: 2 };
+            arena.set_cell_unchecked(pos, Cell::SnakeTail { distance: 2 });
             arena.adjacencies.insert_snake_segment(pos, Direction::Left);
         } else {
-            *cell = Cell::None;
+            arena.set_cell_unchecked(pos, Cell::None);
             arena.adjacencies.insert(pos);
         }
     }
 
     let snake = Snake::default();
-    solver.initialize(&snake, &arena);
+    solver.initialize(&snake, &arena, rng.seed());
     commands.insert_resource(snake);
 }
 
-pub fn setup_solver(mut commands: Commands) {
-    commands.insert_resource(Solver::default());
+pub fn setup_solver(mut commands: Commands, registry: Res<SolverRegistry>, config: Res<Configuration>) {
+    commands.insert_resource(registry.create(config.solver).unwrap_or_default());
 }
 
 pub fn update_snake_direction_human(keys: Res<LastSolverInput>, mut snake: ResMut<Snake>) {
@@ -81,13 +105,24 @@ pub fn update_snake_direction_human(keys: Res<LastSolverInput>, mut snake: ResMu
     }
 }
 
-pub fn compute_snake_direction(arena: Res<Arena>, mut snake: ResMut<Snake>, mut solver: ResMut<Solver>) {
-    let direction = solver.get_direction(&snake, &arena);
+pub fn compute_snake_direction(
+    arena: Res<Arena>,
+    mut snake: ResMut<Snake>,
+    mut solver: ResMut<Solver>,
+    mut profile: ResMut<SolverProfile>,
+    steps: Option<Res<TimeSteps>>,
+) {
+    let interval = steps
+        .and_then(|steps| steps.interval)
+        .filter(|&interval| interval > 0.0);
+    solver.set_step_interval(interval.map(Duration::from_secs_f32));
+
+    let (direction, step) = profiling::measure(|| solver.get_direction(&snake, &arena));
+    profile.record(step);
 
-    if !snake.possible_directions.contains(direction.into()) {
+    if !snake.can_turn(direction) {
         warn!("snake tried to travel in illegal direction");
     }
 
-    snake.possible_directions = !Directions::from(direction.flip());
-    snake.direction = direction;
+    snake.set_direction(direction);
 }
@@ -0,0 +1,363 @@
+//! Region analysis of the arena, built on the [`AdjacencyGraph`] of free cells.
+//!
+//! Tables returned here are indexed by cell, using `y * width + x`.
+
+use std::collections::VecDeque;
+
+use bevy::math::UVec2;
+use smallvec::SmallVec;
+
+use crate::{
+    adjacencies::AdjacencyGraph,
+    arena::{Arena, Cell, Direction},
+    snake::Snake,
+};
+
+fn index(size: UVec2, pos: UVec2) -> usize {
+    (pos.y * size.x + pos.x) as usize
+}
+
+/// Flood fills the graph from `start`, marking every cell it reaches, including `start` itself.
+pub fn reachable(start: UVec2, adjacencies: &AdjacencyGraph) -> Vec<bool> {
+    let size = adjacencies.size();
+    let mut visited = vec![false; (size.x * size.y) as usize];
+    let mut queue = VecDeque::from([start]);
+    visited[index(size, start)] = true;
+
+    while let Some(pos) = queue.pop_front() {
+        for (neighbor, _) in adjacencies.get_neighbors(pos) {
+            if !visited[index(size, neighbor)] {
+                visited[index(size, neighbor)] = true;
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    visited
+}
+
+/// The number of cells reachable from `start`, including `start` itself.
+pub fn reachable_area(start: UVec2, adjacencies: &AdjacencyGraph) -> usize {
+    reachable(start, adjacencies).into_iter().filter(|r| *r).count()
+}
+
+/// The connected components of an [`AdjacencyGraph`].
+#[derive(Debug, Clone)]
+pub struct Components {
+    size: UVec2,
+    labels: Vec<Option<usize>>,
+    sizes: Vec<usize>,
+}
+
+impl Components {
+    /// Labels every node of the graph with the component it belongs to.
+    pub fn new(adjacencies: &AdjacencyGraph) -> Components {
+        let size = adjacencies.size();
+        let mut labels = vec![None; (size.x * size.y) as usize];
+        let mut sizes = Vec::new();
+        let mut queue = VecDeque::new();
+
+        for (start, _) in adjacencies.nodes() {
+            if labels[index(size, start)].is_some() {
+                continue;
+            }
+
+            let label = sizes.len();
+            let mut count = 1;
+            labels[index(size, start)] = Some(label);
+            queue.push_back(start);
+
+            while let Some(pos) = queue.pop_front() {
+                for (neighbor, _) in adjacencies.get_neighbors(pos) {
+                    if labels[index(size, neighbor)].is_none() {
+                        labels[index(size, neighbor)] = Some(label);
+                        count += 1;
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+
+            sizes.push(count);
+        }
+
+        Self { size, labels, sizes }
+    }
+
+    /// The component containing `pos`, or [`None`] if it isn't a node of the graph.
+    pub fn label(&self, pos: UVec2) -> Option<usize> {
+        self.labels.get(index(self.size, pos)).copied().flatten()
+    }
+
+    /// The number of cells in the component containing `pos`, or 0 if it isn't a node.
+    pub fn area(&self, pos: UVec2) -> usize {
+        self.label(pos).map_or(0, |label| self.sizes[label])
+    }
+
+    /// The number of components.
+    pub fn count(&self) -> usize {
+        self.sizes.len()
+    }
+}
+
+/// The nodes and edges of an [`AdjacencyGraph`] whose removal splits the component they're in.
+#[derive(Debug, Clone, Default)]
+pub struct Cuts {
+    pub articulation_points: Vec<UVec2>,
+    /// Each bridge as the cell it starts at and the direction of the cell it leads to.
+    pub bridges: Vec<(UVec2, Direction)>,
+}
+
+impl Cuts {
+    /// Finds every articulation point and bridge using Tarjan's algorithm.
+    pub fn new(adjacencies: &AdjacencyGraph) -> Cuts {
+        struct Frame {
+            pos: UVec2,
+            parent: Option<UVec2>,
+            neighbors: SmallVec<[(UVec2, Direction); 4]>,
+            next: usize,
+        }
+
+        let size = adjacencies.size();
+        let area = (size.x * size.y) as usize;
+
+        // Discovery times start at 1, so that 0 marks unvisited cells.
+        let mut discovered = vec![0; area];
+        let mut low = vec![0; area];
+        let mut cut = vec![false; area];
+        let mut time = 0;
+        let mut cuts = Cuts::default();
+        let mut stack = Vec::new();
+
+        for (root, _) in adjacencies.nodes() {
+            if discovered[index(size, root)] != 0 {
+                continue;
+            }
+
+            time += 1;
+            discovered[index(size, root)] = time;
+            low[index(size, root)] = time;
+
+            let mut root_children = 0;
+            stack.push(Frame {
+                pos: root,
+                parent: None,
+                neighbors: adjacencies.get_neighbors(root),
+                next: 0,
+            });
+
+            while let Some(frame) = stack.last_mut() {
+                let pos = frame.pos;
+
+                if let Some(&(neighbor, _)) = frame.neighbors.get(frame.next) {
+                    frame.next += 1;
+
+                    if Some(neighbor) == frame.parent {
+                        continue;
+                    }
+
+                    if discovered[index(size, neighbor)] == 0 {
+                        time += 1;
+                        discovered[index(size, neighbor)] = time;
+                        low[index(size, neighbor)] = time;
+
+                        if pos == root {
+                            root_children += 1;
+                        }
+
+                        stack.push(Frame {
+                            pos: neighbor,
+                            parent: Some(pos),
+                            neighbors: adjacencies.get_neighbors(neighbor),
+                            next: 0,
+                        });
+                    } else {
+                        low[index(size, pos)] = low[index(size, pos)].min(discovered[index(size, neighbor)]);
+                    }
+
+                    continue;
+                }
+
+                stack.pop();
+
+                let Some(parent) = stack.last().map(|frame| frame.pos) else {
+                    continue;
+                };
+
+                let (i, p) = (index(size, pos), index(size, parent));
+                low[p] = low[p].min(low[i]);
+
+                if low[i] > discovered[p] {
+                    let direction = Direction::from_offset(pos.as_ivec2() - parent.as_ivec2()).unwrap();
+                    cuts.bridges.push((parent, direction));
+                }
+
+                if parent != root && low[i] >= discovered[p] {
+                    cut[p] = true;
+                }
+            }
+
+            if root_children > 1 {
+                cut[index(size, root)] = true;
+            }
+        }
+
+        cuts.articulation_points = (0..area)
+            .filter(|&i| cut[i])
+            .map(|i| UVec2::new(i as u32 % size.x, i as u32 / size.x))
+            .collect();
+
+        cuts
+    }
+}
+
+/// Computes how many steps it takes until each cell is free of the snake, assuming it doesn't eat
+/// in the meantime, with 0 for cells which are already free.
+///
+/// Segments leave in order of their [`Cell::SnakeTail`] distance, starting with the tail, but only
+/// once the snake has finished growing from the last food it ate.
+pub fn time_until_free(arena: &Arena, snake: &Snake) -> Vec<usize> {
+    let len = arena.body.len();
+    let growth = (snake.length + 1).saturating_sub(len);
+    let mut times = vec![0; arena.area()];
+
+    for (pos, cell) in arena.cells() {
+        times[index(arena.size, pos)] = match cell {
+            Cell::SnakeHead => len + growth,
+            Cell::SnakeTail { distance } => len - distance + growth,
+            Cell::None | Cell::Food => 0,
+        };
+    }
+
+    times
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        headless::HeadlessGame,
+        settings::Settings,
+        solver::{astar::AstarSolver, Solver},
+    };
+
+    /// Builds a graph from rows of `#` for blocked cells and `.` for free cells, top row first.
+    fn graph(rows: &[&str]) -> AdjacencyGraph {
+        let size = UVec2::new(rows[0].len() as u32, rows.len() as u32);
+        let mut adjacencies = AdjacencyGraph::grid(size);
+
+        for (y, row) in rows.iter().rev().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    adjacencies.remove(UVec2::new(x as u32, y as u32));
+                }
+            }
+        }
+
+        adjacencies
+    }
+
+    #[test]
+    fn components_are_labeled() {
+        let adjacencies = graph(&["..#.", "..#.", "####", "...#"]);
+        let components = Components::new(&adjacencies);
+
+        assert_eq!(components.count(), 3);
+        assert_eq!(components.area(UVec2::new(0, 3)), 4);
+        assert_eq!(components.area(UVec2::new(3, 2)), 2);
+        assert_eq!(components.area(UVec2::new(1, 0)), 3);
+        assert_eq!(components.area(UVec2::new(2, 2)), 0);
+        assert_eq!(components.label(UVec2::new(0, 0)), components.label(UVec2::new(2, 0)));
+        assert_ne!(components.label(UVec2::new(0, 0)), components.label(UVec2::new(0, 3)));
+
+        assert_eq!(reachable_area(UVec2::new(1, 3), &adjacencies), 4);
+        assert_eq!(reachable_area(UVec2::new(3, 3), &adjacencies), 2);
+    }
+
+    #[test]
+    fn cuts_are_found() {
+        // Two 2x2 rooms joined through a single cell, plus a dead end.
+        let adjacencies = graph(&["#.###", "..#..", "....."]);
+        let cuts = Cuts::new(&adjacencies);
+
+        let mut points = cuts.articulation_points.clone();
+        points.sort_by_key(|p| (p.y, p.x));
+        assert_eq!(
+            points,
+            vec![UVec2::new(1, 0), UVec2::new(2, 0), UVec2::new(3, 0), UVec2::new(1, 1)]
+        );
+
+        let mut bridges = cuts
+            .bridges
+            .iter()
+            .map(|&(pos, dir)| {
+                let other = pos + dir;
+                if (pos.y, pos.x) < (other.y, other.x) {
+                    (pos, other)
+                } else {
+                    (other, pos)
+                }
+            })
+            .collect::<Vec<_>>();
+        bridges.sort_by_key(|(p, _)| (p.y, p.x));
+
+        assert_eq!(
+            bridges,
+            vec![
+                (UVec2::new(1, 0), UVec2::new(2, 0)),
+                (UVec2::new(2, 0), UVec2::new(3, 0)),
+                (UVec2::new(1, 1), UVec2::new(1, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn cuts_match_brute_force() {
+        let adjacencies = graph(&["......", ".##.#.", "...#..", "#.#...", "......"]);
+        let cuts = Cuts::new(&adjacencies);
+        let components = Components::new(&adjacencies).count();
+
+        for (pos, _) in adjacencies.nodes() {
+            let mut removed = adjacencies.clone();
+            removed.remove(pos);
+            // Removing a node with no neighbors removes its component instead of splitting it.
+            let isolated = adjacencies.get_directions(pos).is_empty();
+            let splits = Components::new(&removed).count() > components - usize::from(isolated);
+
+            assert_eq!(cuts.articulation_points.contains(&pos), splits, "at {pos}");
+        }
+    }
+
+    #[test]
+    fn cells_free_up_in_time() {
+        let settings = Settings {
+            arena_size: UVec2::new(8, 8),
+            seed: Some(3),
+            ..Default::default()
+        };
+        let mut game = HeadlessGame::new(settings, Solver::new("astar", AstarSolver::default()));
+
+        // Grow the snake a little first.
+        while game.snake().length < 6 && game.step().is_none() {}
+
+        let times = time_until_free(game.arena(), game.snake());
+        let size = game.arena().size;
+
+        for step in 1.. {
+            let length = game.snake().length;
+            if game.step().is_some() || game.snake().length != length {
+                break;
+            }
+
+            for (pos, cell) in game.arena().cells() {
+                let time = times[index(size, pos)];
+                let occupied = matches!(cell, Cell::SnakeHead | Cell::SnakeTail { .. });
+
+                if time > step {
+                    assert!(occupied, "{pos} freed up after {step} steps, expected {time}");
+                } else if time == step {
+                    assert!(!occupied, "{pos} is still occupied after {step} steps");
+                }
+            }
+        }
+    }
+}
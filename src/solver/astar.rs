@@ -5,27 +5,64 @@ use crate::{
     snake::Snake,
 };
 
-use super::{pathfinding::shortest_path, SolveMethod};
+use super::{pathfinding::shortest_path_ordered, safety::Safety, SolveMethod};
 
+/// Settings of the [`AstarSolver`], which take effect on its next move.
+#[derive(Reflect, Debug, Clone)]
+pub struct AstarSettings {
+    pub safety: Safety,
+    /// The directions to prefer, in order, when several paths to the food are equally short.
+    pub tie_break: [Direction; 4],
+}
+
+impl Default for AstarSettings {
+    fn default() -> Self {
+        Self {
+            safety: Safety::ALL,
+            tie_break: [Direction::Up, Direction::Down, Direction::Right, Direction::Left],
+        }
+    }
+}
+
+/// Solver which follows the shortest path to the food, unless [`Safety`] rules out its first move.
 #[derive(Reflect, Debug, Clone, Default)]
 pub struct AstarSolver {
+    pub settings: AstarSettings,
     shortest_path: Option<Vec<Direction>>,
     start: UVec2,
 }
 
 impl SolveMethod for AstarSolver {
     fn get_direction(&mut self, snake: &Snake, arena: &Arena) -> Direction {
-        let shortest = shortest_path(arena.head, arena.food.unwrap(), snake.direction, &arena.adjacencies);
+        let shortest = shortest_path_ordered(
+            arena.head,
+            arena.food.unwrap(),
+            snake.direction,
+            &arena.adjacencies,
+            &self.settings.tie_break,
+        );
+        let preferred = shortest.as_ref().map(|path| path[0]);
+        self.shortest_path = None;
+        self.start = arena.head;
 
-        if let Some(shortest) = shortest {
-            let dir = shortest[0];
-            self.shortest_path = Some(shortest);
-            self.start = arena.head;
-            dir
-        } else {
-            warn!("no shortest path found");
-            snake.direction
+        let Some(dir) = self.settings.safety.choose(snake, arena, preferred) else {
+            match preferred {
+                Some(_) => warn!("shortest path found, but every move was rejected as unsafe"),
+                None => warn!("no shortest path found"),
+            }
+
+            return snake.direction;
+        };
+
+        if preferred == Some(dir) {
+            self.shortest_path = shortest;
         }
+
+        dir
+    }
+
+    fn settings_mut(&mut self) -> Option<&mut dyn Reflect> {
+        Some(&mut self.settings)
     }
 
     fn debug_paths(&self, _arena: &Arena) -> Vec<(UVec2, Option<&[Direction]>)> {
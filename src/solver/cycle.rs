@@ -0,0 +1,668 @@
+//! Generators for Hamiltonian cycles through every cell of an arena.
+//!
+//! Every generator returns a table with one [`CycleCell`] per cell, indexed by `y * width + x`,
+//! with the cycle starting at index 0 on the given `start` cell.
+
+use std::{
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+use bevy::{
+    math::{IVec2, UVec2},
+    reflect::Reflect,
+};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::arena::{Direction, Directions};
+
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+#[derive(Reflect, Debug, Clone, Copy, Default)]
+pub struct CycleCell {
+    pub index: usize,
+    pub direction: Direction,
+}
+
+/// Builds a cycle which runs along the bottom row, zigzags through every other column row by row,
+/// and returns down the first column, oriented so that the row containing `start` runs to the
+/// left.
+///
+/// Only arenas with an even height of at least 2 and a start at least one cell away from the left
+/// edge admit this cycle.
+pub fn zigzag(size: UVec2, start: UVec2) -> Option<Vec<CycleCell>> {
+    if size.y % 2 != 0 || size.x < 2 || start.x == 0 {
+        return None;
+    }
+
+    // In the unflipped cycle the odd rows run to the left, so flip the cycle vertically if the
+    // start lies on an even row.
+    let flip = start.y % 2 == 0;
+
+    let mut cycle = vec![CycleCell::default(); (size.x * size.y) as usize];
+
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let direction = if y == 0 {
+                if x < size.x - 1 {
+                    Direction::Right
+                } else {
+                    Direction::Up
+                }
+            } else if x == 0 {
+                Direction::Down
+            } else if y % 2 == 1 {
+                if x > 1 || y == size.y - 1 {
+                    Direction::Left
+                } else {
+                    Direction::Up
+                }
+            } else if x < size.x - 1 {
+                Direction::Right
+            } else {
+                Direction::Up
+            };
+
+            let (y, direction) = if flip {
+                let direction = if direction.is_vertical() {
+                    direction.flip()
+                } else {
+                    direction
+                };
+                (size.y - 1 - y, direction)
+            } else {
+                (y, direction)
+            };
+
+            cycle[(y * size.x + x) as usize].direction = direction;
+        }
+    }
+
+    number(size, &mut cycle, start);
+
+    Some(cycle)
+}
+
+/// Builds a cycle by walking counterclockwise around a random spanning tree of the arena at half
+/// resolution, so that every 2x2 block of cells is one node of the tree.
+///
+/// Only arenas with an even width and height admit this cycle.
+pub fn spanning_tree(size: UVec2, start: UVec2, rng: &mut impl Rng) -> Option<Vec<CycleCell>> {
+    if size.x == 0 || size.y == 0 || size.x % 2 != 0 || size.y % 2 != 0 {
+        return None;
+    }
+
+    let half = size / 2;
+    let tree = random_spanning_tree(half, rng);
+    let mut cycle = vec![CycleCell::default(); (size.x * size.y) as usize];
+
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let edges = tree[((y / 2) * half.x + x / 2) as usize];
+
+            // Each block is walked around counterclockwise, leaving through a side only where the
+            // tree has an edge to the neighboring block.
+            let direction = match (x % 2, y % 2) {
+                (0, 0) if edges.down() => Direction::Down,
+                (0, 0) => Direction::Right,
+                (1, 0) if edges.right() => Direction::Right,
+                (1, 0) => Direction::Up,
+                (1, _) if edges.up() => Direction::Up,
+                (1, _) => Direction::Left,
+                _ if edges.left() => Direction::Left,
+                _ => Direction::Down,
+            };
+
+            cycle[(y * size.x + x) as usize].direction = direction;
+        }
+    }
+
+    number(size, &mut cycle, start);
+
+    Some(cycle)
+}
+
+/// Generates a random spanning tree of a grid of the given `size` using Kruskal's algorithm,
+/// returning the tree edges of every node.
+fn random_spanning_tree(size: UVec2, rng: &mut impl Rng) -> Vec<Directions> {
+    let index = |pos: UVec2| (pos.y * size.x + pos.x) as usize;
+
+    let mut edges = Vec::new();
+    for y in 0..size.y {
+        for x in 0..size.x {
+            if x < size.x - 1 {
+                edges.push((UVec2::new(x, y), Direction::Right));
+            }
+
+            if y < size.y - 1 {
+                edges.push((UVec2::new(x, y), Direction::Up));
+            }
+        }
+    }
+    edges.shuffle(rng);
+
+    let mut parents = (0..(size.x * size.y) as usize).collect::<Vec<_>>();
+    let mut tree = vec![Directions::NONE; parents.len()];
+
+    fn root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    for (pos, direction) in edges {
+        let neighbor = pos + direction;
+        let (a, b) = (root(&mut parents, index(pos)), root(&mut parents, index(neighbor)));
+
+        if a != b {
+            parents[a] = b;
+            tree[index(pos)] |= direction.into();
+            tree[index(neighbor)] |= direction.flip().into();
+        }
+    }
+
+    tree
+}
+
+/// How many backbite moves per cell to try at most while closing the path into a cycle. Closing
+/// takes a few moves per cell on average, growing slowly with the size of the arena.
+const MAX_CLOSING_MOVES_PER_CELL: usize = 256;
+
+/// Builds a random cycle by applying backbite moves to a Hamiltonian path through the arena.
+///
+/// A backbite move joins one end of the path to a neighboring cell further along the path and
+/// breaks the path just after that cell, which reverses the part of the path in between. After
+/// `moves` random moves, the path keeps being backbitten until its ends lie next to each other,
+/// at which point it's closed into a cycle.
+///
+/// Only arenas with an even area which are at least 2 cells wide and high admit a cycle. Returns
+/// [`None`] if no cycle was found after many more moves than requested.
+pub fn backbite(size: UVec2, start: UVec2, moves: usize, rng: &mut impl Rng) -> Option<Vec<CycleCell>> {
+    let area = (size.x * size.y) as usize;
+
+    if size.x < 2 || size.y < 2 || area % 2 != 0 {
+        return None;
+    }
+
+    let index = |pos: UVec2| (pos.y * size.x + pos.x) as usize;
+
+    // Start from a path which zigzags through the rows.
+    let mut path = (0..size.y)
+        .flat_map(|y| {
+            (0..size.x).map(move |x| {
+                if y % 2 == 0 {
+                    UVec2::new(x, y)
+                } else {
+                    UVec2::new(size.x - 1 - x, y)
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut positions = vec![0; area];
+    for (i, &pos) in path.iter().enumerate() {
+        positions[index(pos)] = i;
+    }
+
+    let max_moves = moves + area * MAX_CLOSING_MOVES_PER_CELL;
+
+    for count in 0.. {
+        let (first, last) = (path[0], path[area - 1]);
+        if count >= moves && (first.as_ivec2() - last.as_ivec2()).abs().element_sum() == 1 {
+            break;
+        }
+
+        if count >= max_moves {
+            return None;
+        }
+
+        // Only ever bite with the last cell of the path, reversing the whole path at random so
+        // that both ends move.
+        if rng.gen_bool(0.5) {
+            path.reverse();
+            for (i, &pos) in path.iter().enumerate() {
+                positions[index(pos)] = i;
+            }
+        }
+
+        let end = path[area - 1];
+        let next = end.as_ivec2() + DIRECTIONS.choose(rng).unwrap().offset();
+
+        if next.cmplt(IVec2::ZERO).any() || next.cmpge(size.as_ivec2()).any() {
+            continue;
+        }
+
+        let i = positions[index(next.as_uvec2())];
+        if i == area - 2 {
+            continue;
+        }
+
+        path[i + 1..].reverse();
+        for (j, &pos) in path.iter().enumerate().skip(i + 1) {
+            positions[index(pos)] = j;
+        }
+    }
+
+    let mut cycle = vec![CycleCell::default(); area];
+
+    for (i, &pos) in path.iter().enumerate() {
+        let next = path[(i + 1) % area];
+        cycle[index(pos)].direction = Direction::from_offset(next.as_ivec2() - pos.as_ivec2()).unwrap();
+    }
+
+    number(size, &mut cycle, start);
+
+    Some(cycle)
+}
+
+/// Returns the same cycle as `cycle`, but running in the opposite direction, numbered from
+/// `start`.
+pub fn reverse(size: UVec2, cycle: &[CycleCell], start: UVec2) -> Vec<CycleCell> {
+    let mut reversed = vec![CycleCell::default(); cycle.len()];
+
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let direction = cycle[(y * size.x + x) as usize].direction;
+            let next = UVec2::new(x, y) + direction;
+            reversed[(next.y * size.x + next.x) as usize].direction = direction.flip();
+        }
+    }
+
+    number(size, &mut reversed, start);
+
+    reversed
+}
+
+/// Checks whether every position of `path` is followed by the next one along `cycle`.
+pub fn follows(size: UVec2, cycle: &[CycleCell], path: impl IntoIterator<Item = UVec2>) -> bool {
+    let mut path = path.into_iter();
+    let Some(mut current) = path.next() else {
+        return true;
+    };
+
+    for next in path {
+        if current + cycle[(current.y * size.x + current.x) as usize].direction != next {
+            return false;
+        }
+
+        current = next;
+    }
+
+    true
+}
+
+/// Numbers the cells of `cycle` by following its directions, starting at 0 on `start`.
+fn number(size: UVec2, cycle: &mut [CycleCell], start: UVec2) {
+    let mut current = start;
+
+    for index in 0..cycle.len() {
+        let cell = &mut cycle[(current.y * size.x + current.x) as usize];
+        cell.index = index;
+        current = current + cell.direction;
+    }
+}
+
+/// Checks that `cycle` is a single closed tour through every cell of an arena of the given
+/// `size`, with indices increasing by one along the tour.
+pub fn validate(size: UVec2, cycle: &[CycleCell]) -> Result<(), String> {
+    let area = (size.x * size.y) as usize;
+
+    if cycle.len() != area {
+        return Err(format!("cycle has {} cells, expected {}", cycle.len(), area));
+    }
+
+    let mut visited = vec![false; area];
+    let mut current = UVec2::ZERO;
+
+    for _ in 0..area {
+        let cell = cycle[(current.y * size.x + current.x) as usize];
+        let next = current.as_ivec2() + cell.direction.offset();
+
+        if next.x < 0 || next.y < 0 || next.x >= size.x as i32 || next.y >= size.y as i32 {
+            return Err(format!("cycle leaves the arena at {}", current));
+        }
+
+        let next = next.as_uvec2();
+        let next_cell = cycle[(next.y * size.x + next.x) as usize];
+
+        if next_cell.index != (cell.index + 1) % area {
+            return Err(format!(
+                "index jumps from {} at {} to {} at {}",
+                cell.index, current, next_cell.index, next
+            ));
+        }
+
+        visited[(current.y * size.x + current.x) as usize] = true;
+        current = next;
+    }
+
+    if current != UVec2::ZERO {
+        return Err(String::from("cycle doesn't return to its start"));
+    }
+
+    if let Some(i) = visited.iter().position(|v| !v) {
+        return Err(format!(
+            "cycle never visits {}",
+            UVec2::new(i as u32 % size.x, i as u32 / size.x)
+        ));
+    }
+
+    Ok(())
+}
+
+/// Formats `cycle` as a grid of arrows pointing to the next cell of the cycle, one line per row
+/// with the top row first.
+pub fn to_arrows(size: UVec2, cycle: &[CycleCell]) -> String {
+    let mut text = String::with_capacity(((size.x + 1) * size.y) as usize);
+
+    for y in (0..size.y).rev() {
+        for x in 0..size.x {
+            text.push(match cycle[(y * size.x + x) as usize].direction {
+                Direction::Up => '^',
+                Direction::Down => 'v',
+                Direction::Left => '<',
+                Direction::Right => '>',
+            });
+        }
+
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Parses a cycle from either a grid of arrows as written by [`to_arrows`], or a table of the
+/// index of every cell along the cycle separated by whitespace, both with the top row first.
+///
+/// Returns the size of the arena along with the cycle, which is checked with [`validate`]. Cycles
+/// given as arrows are numbered from the bottom-left cell.
+pub fn parse(text: &str) -> Result<(UVec2, Vec<CycleCell>), String> {
+    let lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+
+    let Some(first) = lines.first() else {
+        return Err(String::from("no cycle given"));
+    };
+
+    let indexed = first.starts_with(|c: char| c.is_ascii_digit());
+    let rows = if indexed {
+        parse_indices(&lines)?
+    } else {
+        parse_arrows(&lines)?
+    };
+
+    let size = UVec2::new(rows[0].len() as u32, rows.len() as u32);
+
+    if let Some(y) = rows.iter().position(|row| row.len() != rows[0].len()) {
+        return Err(format!(
+            "row {} has {} cells, expected {}",
+            y + 1,
+            rows[y].len(),
+            rows[0].len()
+        ));
+    }
+
+    let mut cycle = rows.into_iter().rev().flatten().collect::<Vec<_>>();
+
+    if indexed {
+        directions_from_indices(size, &mut cycle)?;
+    } else {
+        for (i, cell) in cycle.iter().enumerate() {
+            let pos = UVec2::new(i as u32 % size.x, i as u32 / size.x);
+            let next = pos.as_ivec2() + cell.direction.offset();
+
+            if next.cmplt(IVec2::ZERO).any() || next.cmpge(size.as_ivec2()).any() {
+                return Err(format!("cycle leaves the arena at {}", pos));
+            }
+        }
+
+        number(size, &mut cycle, UVec2::ZERO);
+    }
+
+    validate(size, &cycle)?;
+
+    Ok((size, cycle))
+}
+
+fn parse_arrows(lines: &[&str]) -> Result<Vec<Vec<CycleCell>>, String> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(y, line)| {
+            line.chars()
+                .map(|c| {
+                    let direction = match c {
+                        '^' => Direction::Up,
+                        'v' => Direction::Down,
+                        '<' => Direction::Left,
+                        '>' => Direction::Right,
+                        c => return Err(format!("unexpected `{}` on row {}", c, y + 1)),
+                    };
+
+                    Ok(CycleCell { index: 0, direction })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn parse_indices(lines: &[&str]) -> Result<Vec<Vec<CycleCell>>, String> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(y, line)| {
+            line.split_whitespace()
+                .map(|word| {
+                    let index = word
+                        .parse()
+                        .map_err(|_| format!("unexpected `{}` on row {}", word, y + 1))?;
+
+                    Ok(CycleCell {
+                        index,
+                        direction: Direction::default(),
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Points every cell of `cycle` at the neighbor with the next index.
+fn directions_from_indices(size: UVec2, cycle: &mut [CycleCell]) -> Result<(), String> {
+    let area = cycle.len();
+
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let pos = UVec2::new(x, y);
+            let next = (cycle[(y * size.x + x) as usize].index + 1) % area;
+
+            let direction = DIRECTIONS
+                .into_iter()
+                .find(|direction| {
+                    let neighbor = pos.as_ivec2() + direction.offset();
+
+                    neighbor.cmpge(IVec2::ZERO).all()
+                        && neighbor.cmplt(size.as_ivec2()).all()
+                        && cycle[(neighbor.y as u32 * size.x + neighbor.x as u32) as usize].index == next
+                })
+                .ok_or_else(|| format!("no neighbor of {} has index {}", pos, next))?;
+
+            cycle[(y * size.x + x) as usize].direction = direction;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a cycle from the file at `path` using [`parse`].
+pub fn load(path: impl AsRef<Path>) -> io::Result<(UVec2, Vec<CycleCell>)> {
+    let path = path.as_ref();
+    let contents = read(path)?;
+
+    parse(&contents).map_err(|e| io::Error::new(ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+}
+
+/// Writes `cycle` to the file at `path` as a grid of arrows.
+pub fn save(path: impl AsRef<Path>, size: UVec2, cycle: &[CycleCell]) -> io::Result<()> {
+    write(path.as_ref(), &to_arrows(size, cycle))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(path: &Path) -> io::Result<String> {
+    std::fs::read_to_string(path)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(path: &Path, contents: &str) -> io::Result<()> {
+    std::fs::write(path, contents)
+}
+
+/// There is no file system on the web, so cycles can't be loaded from or saved to files there.
+#[cfg(target_arch = "wasm32")]
+fn read(_path: &Path) -> io::Result<String> {
+    Err(io::Error::new(
+        ErrorKind::Unsupported,
+        "cycle files aren't supported on the web",
+    ))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(_path: &Path, _contents: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        ErrorKind::Unsupported,
+        "cycle files aren't supported on the web",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn check(size: UVec2, start: UVec2, cycle: Option<Vec<CycleCell>>) {
+        let cycle = cycle.unwrap_or_else(|| panic!("no cycle for {size}"));
+
+        if let Err(e) = validate(size, &cycle) {
+            panic!("invalid cycle for {size}: {e}");
+        }
+
+        assert_eq!(cycle[(start.y * size.x + start.x) as usize].index, 0);
+    }
+
+    #[test]
+    fn zigzag_cycles_are_valid() {
+        for width in 2..12 {
+            for height in (2..12).step_by(2) {
+                let size = UVec2::new(width, height);
+
+                for start in [UVec2::new(1, 0), UVec2::new(width - 1, height - 1), UVec2::new(1, 1)] {
+                    check(size, start, zigzag(size, start));
+                }
+            }
+        }
+
+        assert!(zigzag(UVec2::new(4, 5), UVec2::new(1, 1)).is_none());
+    }
+
+    #[test]
+    fn spanning_tree_cycles_are_valid() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for width in (2..16).step_by(2) {
+            for height in (2..16).step_by(2) {
+                let size = UVec2::new(width, height);
+                let start = UVec2::new(rng.gen_range(0..width), rng.gen_range(0..height));
+
+                check(size, start, spanning_tree(size, start, &mut rng));
+            }
+        }
+
+        assert!(spanning_tree(UVec2::new(4, 3), UVec2::ZERO, &mut rng).is_none());
+    }
+
+    #[test]
+    fn reversed_cycles_are_valid() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let size = UVec2::new(8, 6);
+        let start = UVec2::new(3, 2);
+        let cycle = spanning_tree(size, start, &mut rng).unwrap();
+        let reversed = reverse(size, &cycle, start);
+
+        check(size, start, Some(reversed.clone()));
+
+        let path = (0..size.x).map(|x| UVec2::new(x, 0)).collect::<Vec<_>>();
+        let reversed_path = path.iter().rev().copied().collect::<Vec<_>>();
+        let zigzag = zigzag(size, UVec2::new(1, 1)).unwrap();
+
+        assert!(follows(size, &zigzag, path.iter().copied()));
+        assert!(!follows(size, &zigzag, reversed_path.iter().copied()));
+        assert!(follows(size, &reverse(size, &zigzag, start), reversed_path));
+    }
+
+    #[test]
+    fn cycles_round_trip_through_arrows() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let size = UVec2::new(6, 4);
+        let cycle = spanning_tree(size, UVec2::ZERO, &mut rng).unwrap();
+        let (parsed_size, parsed) = parse(&to_arrows(size, &cycle)).unwrap();
+
+        assert_eq!(parsed_size, size);
+        for (a, b) in cycle.iter().zip(parsed.iter()) {
+            assert_eq!(a.index, b.index);
+            assert_eq!(a.direction, b.direction);
+        }
+    }
+
+    #[test]
+    fn cycles_parse_from_indices() {
+        let (size, cycle) = parse(
+            "
+            3 2
+            0 1
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(size, UVec2::new(2, 2));
+        assert_eq!(cycle[0].direction, Direction::Right);
+        assert_eq!(cycle[3].direction, Direction::Left);
+    }
+
+    #[test]
+    fn invalid_cycles_are_rejected() {
+        // Two separate loops.
+        assert!(parse(">v>v\n^<^<").is_err());
+        // Leaves the arena.
+        assert!(parse("<v\n^<").is_err());
+        // Rows of different lengths.
+        assert!(parse(">v\n^<<").is_err());
+        assert!(parse(">x\n^<").is_err());
+        assert!(parse("0 1\n3 3").is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn backbite_cycles_are_valid() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for width in 2..12 {
+            for height in 2..12 {
+                let size = UVec2::new(width, height);
+                let start = UVec2::new(rng.gen_range(0..width), rng.gen_range(0..height));
+                let cycle = backbite(size, start, (width * height * 10) as usize, &mut rng);
+
+                if (width * height) % 2 == 0 {
+                    check(size, start, cycle);
+                } else {
+                    assert!(cycle.is_none());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    io,
+    process::{Child, Command, Stdio},
+    time::Duration,
+};
+
+use bevy::{
+    log::{error, warn},
+    reflect::Reflect,
+};
+
+use crate::{
+    arena::{Arena, Direction},
+    protocol::{Connection, Message},
+    snake::Snake,
+};
+
+use super::SolveMethod;
+
+/// Solver which asks a separate program for every move, using the line-based JSON protocol in
+/// [`protocol`].
+///
+/// The program is started at the beginning of every game, and keeps running after the game ends
+/// until the next game starts or the solver is replaced. Whenever it doesn't reply in time, or has
+/// stopped working, the snake keeps moving straight ahead.
+#[derive(Reflect, Default)]
+pub struct ExternalSolver {
+    pub settings: ExternalSettings,
+    #[reflect(ignore)]
+    bot: Option<Bot>,
+}
+
+/// Settings of the [`ExternalSolver`]. The command takes effect once the next game starts.
+#[derive(Reflect, Debug, Clone)]
+pub struct ExternalSettings {
+    /// The program to run followed by its arguments, which are passed on as they are.
+    pub command: Vec<String>,
+    /// How long to wait for each move, in milliseconds.
+    pub timeout_ms: u64,
+}
+
+impl Default for ExternalSettings {
+    fn default() -> Self {
+        Self {
+            command: Vec::new(),
+            timeout_ms: 250,
+        }
+    }
+}
+
+impl ExternalSolver {
+    /// An external solver running `command`, as in [`ExternalSettings::command`].
+    pub fn new(command: impl IntoIterator<Item = impl Into<String>>) -> ExternalSolver {
+        Self {
+            settings: ExternalSettings {
+                command: command.into_iter().map(Into::into).collect(),
+                ..Default::default()
+            },
+            bot: None,
+        }
+    }
+}
+
+impl SolveMethod for ExternalSolver {
+    fn initialize(&mut self, _snake: &Snake, arena: &Arena, seed: u64) {
+        // Stop the previous bot before starting a new one.
+        self.bot = None;
+
+        let bot = Bot::spawn(&self.settings.command).and_then(|mut bot| {
+            bot.connection.send(&Message::start(arena, seed))?;
+            Ok(bot)
+        });
+
+        match bot {
+            Ok(bot) => self.bot = Some(bot),
+            Err(e) => error!("failed to start bot `{}`: {}", self.settings.command.join(" "), e),
+        }
+    }
+
+    fn get_direction(&mut self, snake: &Snake, arena: &Arena) -> Direction {
+        let Some(bot) = &mut self.bot else {
+            return snake.direction;
+        };
+
+        let timeout = Duration::from_millis(self.settings.timeout_ms);
+
+        match bot.connection.request(&Message::state(snake, arena), timeout) {
+            Ok(Some(reply)) => reply.direction,
+            Ok(None) => {
+                warn!("bot didn't reply within {:?}", timeout);
+                snake.direction
+            },
+            Err(e) => {
+                error!("bot stopped working: {}", e);
+                self.bot = None;
+                snake.direction
+            },
+        }
+    }
+
+    fn settings_mut(&mut self) -> Option<&mut dyn Reflect> {
+        Some(&mut self.settings)
+    }
+}
+
+/// A running bot program, which is killed when dropped.
+struct Bot {
+    child: Child,
+    connection: Connection,
+}
+
+impl Bot {
+    fn spawn(command: &[String]) -> io::Result<Bot> {
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no command given"))?;
+
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        Ok(Self {
+            child,
+            connection: Connection::new(stdout, stdin),
+        })
+    }
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::UVec2;
+
+    use super::*;
+    use crate::{headless::HeadlessGame, settings::Settings, solver::Solver};
+
+    fn game(solver: ExternalSolver) -> HeadlessGame {
+        let settings = Settings {
+            arena_size: UVec2::new(10, 10),
+            seed: Some(0),
+            ..Default::default()
+        };
+
+        HeadlessGame::new(settings, Solver::new("external", solver))
+    }
+
+    fn python() -> bool {
+        Command::new("python3").arg("--version").output().is_ok()
+    }
+
+    #[test]
+    fn example_bot_finds_food() {
+        if !python() {
+            eprintln!("skipping, python3 is not available");
+            return;
+        }
+
+        let bot = concat!(env!("CARGO_MANIFEST_DIR"), "/bots/follow_food.py");
+        let mut solver = ExternalSolver::new(["python3", bot]);
+        solver.settings.timeout_ms = 5000;
+
+        let mut game = game(solver);
+        while game.step().is_none() {}
+
+        assert!(game.snake().length > 5, "only reached length {}", game.snake().length);
+    }
+
+    #[test]
+    fn arguments_are_passed_on_unsplit() {
+        if !python() {
+            eprintln!("skipping, python3 is not available");
+            return;
+        }
+
+        // Only runs if the script arrives as a single argument, spaces and all.
+        let script = "import json, sys\nfor line in sys.stdin:\n    state = json.loads(line)\n    if state['type'] == 'state':\n        print(json.dumps({'direction': 'up', 'steps': state['steps']}), flush=True)";
+        let mut solver = ExternalSolver::new(["python3", "-c", script]);
+        solver.settings.timeout_ms = 5000;
+
+        let mut game = game(solver);
+        game.step();
+
+        assert_eq!(game.snake().direction, Direction::Up);
+    }
+
+    #[test]
+    fn slow_bots_move_straight_ahead() {
+        let mut solver = ExternalSolver::new(["sleep", "10"]);
+        solver.settings.timeout_ms = 10;
+
+        let mut game = game(solver);
+        let direction = game.snake().direction;
+        game.step();
+
+        assert_eq!(game.snake().direction, direction);
+        assert_eq!(game.snake().steps, 1);
+    }
+}
@@ -2,11 +2,14 @@ use bevy::prelude::*;
 
 use crate::{
     arena::{Arena, Direction},
+    simulation::Simulation,
     snake::Snake,
 };
 
 use super::{
-    pathfinding::{longest_path, shortest_path},
+    analysis,
+    pathfinding::{longest_path, shortest_path, timed_shortest_path},
+    safety::Safety,
     SolveMethod,
 };
 
@@ -19,51 +22,100 @@ use super::{
 /// longest path from its head to its tail, which can cause it to lose by running into its tail.
 #[derive(Reflect, Debug, Clone, Default)]
 pub struct GreedySolver {
+    pub settings: GreedySettings,
     shortest_path: Option<Vec<Direction>>,
     longest_path: Option<Vec<Direction>>,
     head: UVec2,
     virtual_tail: Option<UVec2>,
 }
 
+/// Settings of the [`GreedySolver`], which take effect on its next move.
+#[derive(Reflect, Debug, Clone)]
+pub struct GreedySettings {
+    /// Let the path to the food pass through cells the tail will have left by the time the head
+    /// gets there.
+    pub time_aware: bool,
+    /// Only follow the path to the food if a virtual snake which ate it could still reach its
+    /// tail.
+    pub forward_check: bool,
+    /// Checks applied to the chosen move on top of the forward check.
+    pub safety: Safety,
+}
+
+impl Default for GreedySettings {
+    fn default() -> Self {
+        Self {
+            time_aware: true,
+            forward_check: true,
+            safety: Safety::default(),
+        }
+    }
+}
+
 impl SolveMethod for GreedySolver {
     fn get_direction(&mut self, snake: &Snake, arena: &Arena) -> Direction {
-        let shortest = shortest_path(arena.head, arena.food.unwrap(), snake.direction, &arena.adjacencies);
+        let dir = self.plan(snake, arena);
+        let safe = self.settings.safety.choose(snake, arena, Some(dir)).unwrap_or(dir);
+
+        if safe != dir {
+            self.shortest_path = None;
+            self.longest_path = None;
+        }
+
+        safe
+    }
+
+    fn settings_mut(&mut self) -> Option<&mut dyn Reflect> {
+        Some(&mut self.settings)
+    }
+
+    fn debug_paths(&self, _arena: &Arena) -> Vec<(UVec2, Option<&[Direction]>)> {
+        vec![
+            (self.head, self.shortest_path.as_deref()),
+            (self.head, self.longest_path.as_deref()),
+        ]
+    }
+
+    fn debug_points(&self, _arena: &Arena) -> Vec<Option<UVec2>> {
+        vec![None, None, self.virtual_tail]
+    }
+}
+
+impl GreedySolver {
+    fn plan(&mut self, snake: &Snake, arena: &Arena) -> Direction {
+        let food = arena.food.unwrap();
+        let shortest = shortest_path(arena.head, food, snake.direction, &arena.adjacencies).or_else(|| {
+            self.settings.time_aware.then(|| {
+                let free_at = analysis::time_until_free(arena, snake);
+                timed_shortest_path(arena.head, food, snake.direction, arena.size, &free_at)
+            })?
+        });
         self.head = arena.head;
         self.longest_path = None;
         self.virtual_tail = None;
 
         if let Some(shortest) = shortest {
-            let mut adjacencies = arena.adjacencies.clone();
-            let mut head = arena.head;
-            let mut tail = arena.tail;
-            let mut behind = arena.behind;
-            let mut behind_behind = arena.behind;
+            let dir = shortest[0];
 
-            // Move a virtual snake to eat the food along the shortest path.
-            for dir in shortest.iter() {
-                adjacencies.remove(head);
-                adjacencies.insert_snake_segment(head, *dir);
-                adjacencies.insert(tail);
-                head = head + dir;
-                behind_behind = behind;
-                behind = tail;
-                tail = tail + adjacencies.get_segment_direction(tail).unwrap();
-                adjacencies.remove_snake_segment(behind);
+            if !self.settings.forward_check {
+                self.shortest_path = Some(shortest);
+                return dir;
             }
 
-            // Account for growth of snake from eating food.
-            if arena.just_ate {
-                adjacencies.remove(behind);
-                behind = behind_behind;
-            }
+            // Move a virtual snake to eat the food along the shortest path.
+            let mut simulation = Simulation::new(snake, arena);
+            let followed = simulation.follow(&shortest);
+            let head = simulation.head();
+            let behind = simulation.behind();
+            let mut adjacencies = simulation.adjacencies().clone();
 
             self.virtual_tail = Some(behind);
 
             // Compute the longest path from the virtual snake's head to its tail.
             let longest = shortest_path(head, behind, shortest[0], &adjacencies)
-                .and_then(|path| longest_path(head, &adjacencies, path));
+                .and_then(|path| longest_path(head, &adjacencies, path))
+                .filter(|_| followed);
 
-            let dir = shortest[0];
             self.shortest_path = Some(shortest);
 
             // If this path exists for the virtual snake, account for growth of virtual snake and
@@ -137,15 +189,4 @@ impl SolveMethod for GreedySolver {
             dir
         }
     }
-
-    fn debug_paths(&self, _arena: &Arena) -> Vec<(UVec2, Option<&[Direction]>)> {
-        vec![
-            (self.head, self.shortest_path.as_deref()),
-            (self.head, self.longest_path.as_deref()),
-        ]
-    }
-
-    fn debug_points(&self, _arena: &Arena) -> Vec<Option<UVec2>> {
-        vec![None, None, self.virtual_tail]
-    }
 }
@@ -1,4 +1,10 @@
-use bevy::{math::UVec2, reflect::Reflect};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{log::error, math::UVec2, reflect::Reflect};
+use rand::{rngs::StdRng, SeedableRng};
 
 use crate::{
     arena::{Arena, Direction},
@@ -6,62 +12,143 @@ use crate::{
 };
 
 use super::{
+    astar::AstarSolver,
+    cycle::{self, CycleCell},
     pathfinding::{longest_path, shortest_path},
     SolveMethod,
 };
 
+/// How many random cycles of each kind to try before moving on to the next way of building one.
+const RANDOM_CYCLE_ATTEMPTS: usize = 64;
+
+/// Mixed into the game seed for generating random cycles, so that they don't draw the same numbers
+/// as the food placement of the [`GameRng`](crate::game::GameRng).
+const CYCLE_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Solver which follows a Hamiltonian cycle through every cell of the arena, taking shortcuts
+/// towards the food while the snake is short.
+///
+/// Only some arenas have such a cycle, as told by [`HamiltonSolver::supports`]. On any other arena, the solver logs an error
+/// and steers with an [`AstarSolver`] instead.
 #[derive(Reflect, Debug, Clone, Default)]
 pub struct HamiltonSolver {
+    pub settings: HamiltonSettings,
     size: UVec2,
     cycle: Vec<CycleCell>,
     head: UVec2,
     shortest_path: Option<Vec<Direction>>,
+    fallback: Option<AstarSolver>,
 }
 
-impl SolveMethod for HamiltonSolver {
-    fn initialize(&mut self, snake: &Snake, arena: &Arena) {
-        self.size = arena.size;
-        self.cycle = vec![CycleCell::default(); (arena.size.x * arena.size.y) as usize];
-        let width = arena.size.x;
+/// Settings of the [`HamiltonSolver`]. The shortcut threshold takes effect on its next move, the
+/// others once the next game starts.
+#[derive(Reflect, Debug, Clone)]
+pub struct HamiltonSettings {
+    /// Start every game on a random cycle seeded from the game seed, instead of the same cycle
+    /// for every game on a given arena.
+    pub random_cycle: bool,
+    /// A file to load the cycle from, as read by [`cycle::load`], used instead of generating one
+    /// whenever it fits the arena and the snake.
+    pub cycle_file: Option<PathBuf>,
+    /// The fraction of the arena the snake may fill while still taking shortcuts off the cycle
+    /// towards the food, with 0 never taking shortcuts.
+    pub shortcut_threshold: f32,
+}
+
+impl Default for HamiltonSettings {
+    fn default() -> Self {
+        Self {
+            random_cycle: true,
+            cycle_file: None,
+            shortcut_threshold: 0.5,
+        }
+    }
+}
 
-        let path = shortest_path(arena.head, arena.behind, snake.direction, &arena.adjacencies)
-            .and_then(|path| longest_path(arena.head, &arena.adjacencies, path))
-            .unwrap(); // Cannot fail; the initial state always has a valid path to tail.
+impl HamiltonSolver {
+    /// Whether an arena of the given `size` has a Hamiltonian cycle through the snake as it starts
+    /// out along the middle row. That's every arena at least 3x2 with an even area, except for
+    /// those 3 cells high, where no cycle runs along the middle row, and those 3 cells wide and
+    /// more than 2 cells high, which the snake cuts in two.
+    pub fn supports(size: UVec2) -> bool {
+        let area = size.x * size.y;
+        let blocked = size.y == 3 || (size.x == 3 && size.y > 2);
+        area % 2 == 0 && size.x >= 3 && size.y >= 2 && !blocked
+    }
 
-        let mut current = arena.head;
-        let mut count = 0;
+    /// The Hamiltonian cycle the solver follows, indexed by cell, which is empty while it falls
+    /// back to an [`AstarSolver`].
+    pub fn cycle(&self) -> &[CycleCell] {
+        &self.cycle
+    }
 
-        for direction in path {
-            self.cycle[(current.y * width + current.x) as usize].index = count;
-            self.cycle[(current.y * width + current.x) as usize].direction = direction;
-            current = current + direction;
-            count += 1;
+    /// Writes the current cycle to the file at `path` as a grid of arrows.
+    pub fn save_cycle(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if self.cycle.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "the solver doesn't follow a cycle",
+            ));
         }
 
-        // Process snake segments
-        current = arena.behind;
-        for _ in 0..snake.length + 1 {
-            self.cycle[(current.y * width + current.x) as usize].index = count;
-            self.cycle[(current.y * width + current.x) as usize].direction = snake.direction;
-            current = current + snake.direction;
-            count += 1;
+        cycle::save(path, self.size, &self.cycle)
+    }
+}
+
+impl SolveMethod for HamiltonSolver {
+    fn initialize(&mut self, snake: &Snake, arena: &Arena, seed: u64) {
+        self.size = arena.size;
+        self.fallback = None;
+
+        let cycle = self
+            .settings
+            .cycle_file
+            .as_ref()
+            .and_then(|path| loaded_cycle(path, arena))
+            .or_else(|| self.settings.random_cycle.then(|| random_cycle(arena, seed)).flatten())
+            .or_else(|| stretched_cycle(snake, arena))
+            .filter(|cycle| cycle::validate(arena.size, cycle).is_ok())
+            .or_else(|| cycle::zigzag(arena.size, arena.head))
+            .or_else(|| backbitten_cycle(arena, seed));
+
+        match cycle {
+            Some(cycle) => self.cycle = cycle,
+            None => {
+                error!(
+                    "no Hamiltonian cycle through the snake on a {}x{} arena, falling back to A*",
+                    arena.size.x, arena.size.y
+                );
+
+                let mut fallback = AstarSolver::default();
+                fallback.initialize(snake, arena, seed);
+                self.cycle = Vec::new();
+                self.fallback = Some(fallback);
+            },
         }
     }
 
     fn get_direction(&mut self, snake: &Snake, arena: &Arena) -> Direction {
+        if let Some(fallback) = &mut self.fallback {
+            return fallback.get_direction(snake, arena);
+        }
+
         let mut dir = self.cycle[(arena.head.y * self.size.x + arena.head.x) as usize].direction;
         let food_pos = arena.food.unwrap();
         self.shortest_path = None;
         self.head = arena.head;
 
+        let area = (self.size.x * self.size.y) as usize;
         let dist = |p1: usize, mut p2: usize| {
             if p1 > p2 {
-                p2 += (self.size.x * self.size.y) as usize;
+                p2 += area;
             }
             p2 - p1
         };
 
-        if (snake.length as u32) < arena.size.x * arena.size.y / 2 {
+        // Rounded down, so that the default threshold of 0.5 allows shortcuts below exactly `area / 2`.
+        let shortcut_limit = (area as f32 * self.settings.shortcut_threshold) as usize;
+
+        if snake.length < shortcut_limit {
             if let Some(shortest) = shortest_path(arena.head, arena.food.unwrap(), snake.direction, &arena.adjacencies)
             {
                 let next_pos = arena.head + shortest[0];
@@ -75,7 +162,12 @@ impl SolveMethod for HamiltonSolver {
                     let next_dist = dist(tail, next);
                     let food_dist = dist(tail, food);
 
-                    if (next_dist > head_dist) && (next_dist <= food_dist) {
+                    // Skipping ahead leaves gaps in the cycle behind the head which are only freed
+                    // once the tail passes them, so keep enough room ahead of the head for the
+                    // snake to grow into until then.
+                    let room = area - next_dist - 1;
+
+                    if (next_dist > head_dist) && (next_dist <= food_dist) && (room > snake.length + 1) {
                         dir = shortest[0];
                         self.shortest_path = Some(shortest);
                     }
@@ -86,17 +178,139 @@ impl SolveMethod for HamiltonSolver {
         dir
     }
 
-    fn debug_paths(&self, _arena: &Arena) -> Vec<(UVec2, Option<&[Direction]>)> {
+    fn settings_mut(&mut self) -> Option<&mut dyn Reflect> {
+        Some(&mut self.settings)
+    }
+
+    fn debug_paths(&self, arena: &Arena) -> Vec<(UVec2, Option<&[Direction]>)> {
+        if let Some(fallback) = &self.fallback {
+            return fallback.debug_paths(arena);
+        }
+
         vec![(self.head, self.shortest_path.as_deref())]
     }
 
     fn debug_tables(&self, _arena: &Arena) -> Vec<Option<&[CycleCell]>> {
+        if self.fallback.is_some() {
+            return Vec::new();
+        }
+
         vec![None, Some(&self.cycle)]
     }
 }
 
-#[derive(Reflect, Debug, Clone, Copy, Default)]
-pub struct CycleCell {
-    pub index: usize,
-    pub direction: Direction,
+/// Loads the cycle in the file at `path`, or its reverse if the snake runs against it.
+///
+/// Errors are logged rather than returned, so that the solver can fall back to a generated cycle.
+fn loaded_cycle(path: &Path, arena: &Arena) -> Option<Vec<CycleCell>> {
+    let (size, cycle) = cycle::load(path)
+        .inspect_err(|e| error!("failed to load cycle: {}", e))
+        .ok()?;
+
+    if size != arena.size {
+        error!(
+            "cycle in {} is for a {}x{} arena, not {}x{}",
+            path.display(),
+            size.x,
+            size.y,
+            arena.size.x,
+            arena.size.y
+        );
+        return None;
+    }
+
+    let body = || arena.body.iter().rev().copied();
+
+    if cycle::follows(size, &cycle, body()) {
+        return Some(cycle);
+    }
+
+    let reversed = cycle::reverse(size, &cycle, UVec2::ZERO);
+
+    if cycle::follows(size, &reversed, body()) {
+        return Some(reversed);
+    }
+
+    error!("cycle in {} doesn't run through the snake", path.display());
+    None
+}
+
+/// Builds a cycle around a random spanning tree which the snake already lies along, generating
+/// cycles from `seed` until one fits the snake in either direction.
+fn random_cycle(arena: &Arena, seed: u64) -> Option<Vec<CycleCell>> {
+    let mut rng = StdRng::seed_from_u64(seed ^ CYCLE_SEED);
+    let body = || arena.body.iter().rev().copied();
+
+    for _ in 0..RANDOM_CYCLE_ATTEMPTS {
+        let cycle = cycle::spanning_tree(arena.size, arena.head, &mut rng)?;
+
+        if cycle::follows(arena.size, &cycle, body()) {
+            return Some(cycle);
+        }
+
+        let reversed = cycle::reverse(arena.size, &cycle, arena.head);
+
+        if cycle::follows(arena.size, &reversed, body()) {
+            return Some(reversed);
+        }
+    }
+
+    None
+}
+
+/// Builds a cycle by backbiting from a zigzag path, which works on any arena with an even area,
+/// generating cycles from `seed` until one fits the snake in either direction.
+fn backbitten_cycle(arena: &Arena, seed: u64) -> Option<Vec<CycleCell>> {
+    let mut rng = StdRng::seed_from_u64(seed ^ CYCLE_SEED);
+    let body = || arena.body.iter().rev().copied();
+    let moves = (arena.size.x * arena.size.y) as usize;
+
+    for _ in 0..RANDOM_CYCLE_ATTEMPTS {
+        let cycle = cycle::backbite(arena.size, arena.head, moves, &mut rng)?;
+
+        if cycle::follows(arena.size, &cycle, body()) {
+            return Some(cycle);
+        }
+
+        let reversed = cycle::reverse(arena.size, &cycle, arena.head);
+
+        if cycle::follows(arena.size, &reversed, body()) {
+            return Some(reversed);
+        }
+    }
+
+    None
+}
+
+/// Builds a cycle by stretching the shortest path from the head of the snake around to the cell
+/// behind its tail as far as possible, then closing it through the snake.
+///
+/// The stretched path isn't guaranteed to visit every cell, so the result must be validated.
+fn stretched_cycle(snake: &Snake, arena: &Arena) -> Option<Vec<CycleCell>> {
+    let mut cycle = vec![CycleCell::default(); (arena.size.x * arena.size.y) as usize];
+    let width = arena.size.x;
+
+    let path = shortest_path(arena.head, arena.behind, snake.direction, &arena.adjacencies)
+        .and_then(|path| longest_path(arena.head, &arena.adjacencies, path))?;
+
+    let mut current = arena.head;
+    let mut count = 0;
+
+    for direction in path {
+        cycle[(current.y * width + current.x) as usize].index = count;
+        cycle[(current.y * width + current.x) as usize].direction = direction;
+        current = current + direction;
+        count += 1;
+    }
+
+    // Process snake segments
+    current = arena.behind;
+    for _ in 0..snake.length + 1 {
+        cycle[(current.y * width + current.x) as usize].index = count;
+        cycle[(current.y * width + current.x) as usize].direction = snake.direction;
+        current = current + snake.direction;
+        count += 1;
+    }
+
+    Some(cycle)
 }
@@ -1,26 +1,49 @@
+use std::{fmt, sync::Arc, time::Duration};
+
 use astar::AstarSolver;
-use bevy::prelude::*;
-use bevy_inspector_egui::prelude::*;
+use bevy::{prelude::*, reflect::GetTypeRegistration};
+use cycle::CycleCell;
 use greedy::GreedySolver;
-use hamilton::{CycleCell, HamiltonSolver};
+use hamilton::HamiltonSolver;
 
 use crate::{
     arena::{Arena, Direction},
     snake::Snake,
 };
 
+pub mod analysis;
 pub mod astar;
+pub mod cycle;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod external;
 pub mod greedy;
 pub mod hamilton;
 mod pathfinding;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod remote;
+pub mod safety;
 
-pub trait SolveMethod {
-    fn initialize(&mut self, _snake: &Snake, _arena: &Arena) {
+/// A strategy for steering the snake, which can be registered in a [`SolverRegistry`].
+pub trait SolveMethod: Reflect {
+    /// Prepares the solver for a new game, where `seed` is the seed of the game's
+    /// [`GameRng`](crate::game::GameRng).
+    fn initialize(&mut self, _snake: &Snake, _arena: &Arena, _seed: u64) {
         // Do nothing
     }
 
     fn get_direction(&mut self, snake: &Snake, arena: &Arena) -> Direction;
 
+    /// Called before every step with the time between steps, which is [`None`] when the game steps
+    /// every frame or runs without a window.
+    fn set_step_interval(&mut self, _interval: Option<Duration>) {
+        // Do nothing
+    }
+
+    /// The settings of the solver which can be edited in the inspector while it runs.
+    fn settings_mut(&mut self) -> Option<&mut dyn Reflect> {
+        None
+    }
+
     fn debug_paths(&self, _arena: &Arena) -> Vec<(UVec2, Option<&[Direction]>)> {
         Vec::new()
     }
@@ -34,58 +57,228 @@ pub trait SolveMethod {
     }
 }
 
-#[derive(Reflect, Resource, Debug, Clone, InspectorOptions)]
-#[reflect(Resource, InspectorOptions)]
-pub enum Solver {
-    Astar(AstarSolver),
-    Greedy(GreedySolver),
-    Hamilton(HamiltonSolver),
+/// The solver driving the snake in [`GameMode::Computer`](crate::game::GameMode::Computer), along
+/// with the name it was registered under.
+#[derive(Resource)]
+pub struct Solver {
+    name: &'static str,
+    method: Box<dyn SolveMethod>,
 }
 
 impl Solver {
-    pub fn initialize(&mut self, snake: &Snake, arena: &Arena) {
-        match self {
-            Solver::Astar(s) => s.initialize(snake, arena),
-            Solver::Greedy(s) => s.initialize(snake, arena),
-            Solver::Hamilton(s) => s.initialize(snake, arena),
+    pub fn new(name: &'static str, method: impl SolveMethod) -> Solver {
+        Self {
+            name,
+            method: Box::new(method),
         }
     }
 
+    /// A short, human-readable name for this solver.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The solve method, if it is a `T`.
+    pub fn downcast_ref<T: SolveMethod>(&self) -> Option<&T> {
+        self.method.as_reflect().downcast_ref()
+    }
+
+    pub fn settings_mut(&mut self) -> Option<&mut dyn Reflect> {
+        self.method.settings_mut()
+    }
+
+    pub fn initialize(&mut self, snake: &Snake, arena: &Arena, seed: u64) {
+        self.method.initialize(snake, arena, seed);
+    }
+
     pub fn get_direction(&mut self, snake: &Snake, arena: &Arena) -> Direction {
-        match self {
-            Solver::Astar(s) => s.get_direction(snake, arena),
-            Solver::Greedy(s) => s.get_direction(snake, arena),
-            Solver::Hamilton(s) => s.get_direction(snake, arena),
-        }
+        self.method.get_direction(snake, arena)
+    }
+
+    pub fn set_step_interval(&mut self, interval: Option<Duration>) {
+        self.method.set_step_interval(interval);
     }
 
     pub fn debug_paths(&self, arena: &Arena) -> Vec<(UVec2, Option<&[Direction]>)> {
-        match self {
-            Solver::Astar(s) => s.debug_paths(arena),
-            Solver::Greedy(s) => s.debug_paths(arena),
-            Solver::Hamilton(s) => s.debug_paths(arena),
-        }
+        self.method.debug_paths(arena)
     }
 
     pub fn debug_tables(&self, arena: &Arena) -> Vec<Option<&[CycleCell]>> {
-        match self {
-            Solver::Astar(s) => s.debug_tables(arena),
-            Solver::Greedy(s) => s.debug_tables(arena),
-            Solver::Hamilton(s) => s.debug_tables(arena),
-        }
+        self.method.debug_tables(arena)
     }
 
     pub fn debug_points(&self, arena: &Arena) -> Vec<Option<UVec2>> {
-        match self {
-            Solver::Astar(s) => s.debug_points(arena),
-            Solver::Greedy(s) => s.debug_points(arena),
-            Solver::Hamilton(s) => s.debug_points(arena),
-        }
+        self.method.debug_points(arena)
     }
 }
 
 impl Default for Solver {
     fn default() -> Self {
-        Solver::Hamilton(HamiltonSolver::default())
+        Solver::new("hamilton", HamiltonSolver::default())
+    }
+}
+
+impl fmt::Debug for Solver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Solver")
+            .field("name", &self.name)
+            .field("method", &self.method.as_partial_reflect())
+            .finish()
+    }
+}
+
+type Constructor = Arc<dyn Fn() -> Box<dyn SolveMethod> + Send + Sync>;
+
+/// Every solver that can be picked by name, in the order they were registered.
+///
+/// Solvers, including ones defined outside this crate, are added to an app's registry with
+/// [`AppExtSolvers::register_solver`], and the built-in ones with [`SolversPlugin`].
+#[derive(Resource, Clone, Default)]
+pub struct SolverRegistry {
+    solvers: Vec<(&'static str, Constructor)>,
+}
+
+impl SolverRegistry {
+    /// The solvers registered by [`SolversPlugin`], for playing games without a window.
+    pub fn builtin() -> SolverRegistry {
+        let mut app = App::new();
+        app.add_plugins(SolversPlugin);
+
+        app.world_mut().remove_resource::<SolverRegistry>().unwrap_or_default()
+    }
+
+    /// Registers a solver created by `T::default`, replacing any solver with the same name.
+    pub fn register<T: SolveMethod + Default>(&mut self, name: &'static str) -> &mut Self {
+        self.register_with(name, T::default)
+    }
+
+    /// Registers a solver created by `constructor`, replacing any solver with the same name.
+    pub fn register_with<T: SolveMethod>(
+        &mut self,
+        name: &'static str,
+        constructor: impl Fn() -> T + Send + Sync + 'static,
+    ) -> &mut Self {
+        let constructor: Constructor = Arc::new(move || Box::new(constructor()));
+
+        match self.solvers.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, c)) => *c = constructor,
+            None => self.solvers.push((name, constructor)),
+        }
+
+        self
+    }
+
+    /// The names of every registered solver.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.solvers.iter().map(|(name, _)| *name)
+    }
+
+    /// Creates a new instance of the solver registered as `name`.
+    pub fn create(&self, name: &str) -> Option<Solver> {
+        let (name, constructor) = self.solvers.iter().find(|(n, _)| *n == name)?;
+
+        Some(Solver {
+            name,
+            method: constructor(),
+        })
+    }
+}
+
+/// Registers the built-in solvers.
+pub struct SolversPlugin;
+
+impl Plugin for SolversPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_solver::<AstarSolver>("astar")
+            .register_solver::<GreedySolver>("greedy")
+            .register_solver::<HamiltonSolver>("hamilton");
+    }
+}
+
+/// Registers solvers with an [`App`], making them available to pick in the UI.
+pub trait AppExtSolvers {
+    /// Registers a solver created by `T::default` in the app's [`SolverRegistry`], along with its
+    /// type so that it can be inspected.
+    fn register_solver<T: SolveMethod + Default + GetTypeRegistration>(&mut self, name: &'static str) -> &mut Self;
+
+    /// Registers a solver created by `constructor` in the app's [`SolverRegistry`], along with its
+    /// type so that it can be inspected.
+    fn register_solver_with<T: SolveMethod + GetTypeRegistration>(
+        &mut self,
+        name: &'static str,
+        constructor: impl Fn() -> T + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl AppExtSolvers for App {
+    fn register_solver<T: SolveMethod + Default + GetTypeRegistration>(&mut self, name: &'static str) -> &mut Self {
+        self.register_solver_with(name, T::default)
+    }
+
+    fn register_solver_with<T: SolveMethod + GetTypeRegistration>(
+        &mut self,
+        name: &'static str,
+        constructor: impl Fn() -> T + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.init_resource::<SolverRegistry>()
+            .register_type::<T>()
+            .world_mut()
+            .resource_mut::<SolverRegistry>()
+            .register_with(name, constructor);
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{headless::HeadlessGame, settings::Settings};
+
+    /// Moves in a fixed direction until it runs into something.
+    #[derive(Reflect, Default)]
+    struct StraightSolver;
+
+    impl SolveMethod for StraightSolver {
+        fn get_direction(&mut self, snake: &Snake, _arena: &Arena) -> Direction {
+            snake.direction
+        }
+    }
+
+    #[test]
+    fn registered_solvers_can_be_created() {
+        let mut app = App::new();
+        app.add_plugins(SolversPlugin)
+            .register_solver::<StraightSolver>("straight");
+
+        let mut registry = app.world_mut().resource_mut::<SolverRegistry>();
+        registry.register_with("astar", || {
+            let mut solver = AstarSolver::default();
+            solver.settings.safety = safety::Safety::default();
+            solver
+        });
+
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            ["astar", "greedy", "hamilton", "straight"]
+        );
+        assert!(registry.create("missing").is_none());
+
+        let astar = registry.create("astar").unwrap();
+        assert!(!astar.downcast_ref::<AstarSolver>().unwrap().settings.safety.check_area);
+
+        let solver = registry.create("straight").unwrap();
+        assert_eq!(solver.name(), "straight");
+        assert!(solver.downcast_ref::<HamiltonSolver>().is_none());
+
+        let settings = Settings {
+            arena_size: UVec2::new(6, 6),
+            seed: Some(0),
+            ..Default::default()
+        };
+        let mut game = HeadlessGame::new(settings, solver);
+        while game.step().is_none() {}
+
+        assert_eq!(game.snake().direction, Direction::Left);
     }
 }
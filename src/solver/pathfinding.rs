@@ -2,8 +2,9 @@ use std::collections::BinaryHeap;
 
 use bevy::{math::UVec2, utils::HashSet};
 use indexmap::map::Entry;
+use smallvec::SmallVec;
 
-use crate::{adjacencies::AdjacencyGraph, arena::Direction};
+use crate::{adjacencies::AdjacencyGraph, arena::Direction, profiling};
 
 /// Computes the shortest path from `start` to `goal`, given a starting `direction`.
 ///
@@ -18,7 +19,31 @@ pub(super) fn shortest_path(
     astar(start, goal, direction, adjacencies).map(|v| v.0)
 }
 
+/// Computes the shortest path like [`shortest_path`], but breaks ties between equally short paths
+/// by preferring to move in directions which come earlier in `order`.
+///
+/// Directions missing from `order` are tried last.
+pub(super) fn shortest_path_ordered(
+    start: UVec2,
+    goal: UVec2,
+    direction: Direction,
+    adjacencies: &AdjacencyGraph,
+    order: &[Direction],
+) -> Option<Vec<Direction>> {
+    let rank = |dir: Direction| order.iter().position(|&d| d == dir).unwrap_or(order.len());
+    let neighbors = |pos, _| {
+        let mut neighbors = adjacencies.get_neighbors(pos);
+        neighbors.sort_by_key(|&(_, dir)| rank(dir));
+        neighbors
+    };
+
+    search(start, goal, direction, false, true, neighbors).map(|v| v.0)
+}
+
 /// Computes the longest path from `start` by modifying the given shortest path.
+///
+/// Returns [`None`] if `path` is empty, or if it isn't a path through the graph which never
+/// revisits a cell.
 pub(super) fn longest_path(
     start: UVec2,
     adjacencies: &AdjacencyGraph,
@@ -34,8 +59,15 @@ pub(super) fn longest_path(
     visited.insert(current);
 
     for dir in path.iter() {
-        current = (current.as_ivec2() + dir.offset()).as_uvec2();
-        visited.insert(current);
+        if !adjacencies.get_directions(current).contains((*dir).into()) {
+            return None;
+        }
+
+        current = current + dir;
+
+        if !visited.insert(current) {
+            return None;
+        }
     }
 
     let mut index = 0;
@@ -43,31 +75,19 @@ pub(super) fn longest_path(
 
     loop {
         let cur_dir = path[index];
-        let next = (current.as_ivec2() + cur_dir.offset()).as_uvec2();
+        let next = current + cur_dir;
 
         let test_dirs = if cur_dir.is_horizontal() {
-            Some([Direction::Up, Direction::Down])
-        } else if cur_dir.is_vertical() {
-            Some([Direction::Right, Direction::Left])
+            [Direction::Up, Direction::Down]
         } else {
-            None
+            [Direction::Right, Direction::Left]
         };
 
         let mut extended = false;
-        for (dir, offset) in test_dirs
-            .into_iter()
-            .flat_map(|v| v.into_iter())
-            .map(|dir| (dir, dir.offset()))
-        {
-            let cur_test = current.as_ivec2() + offset;
-            let next_test = next.as_ivec2() + offset;
-
-            if cur_test.x < 0 || cur_test.y < 0 || next_test.x < 0 || next_test.y < 0 {
+        for dir in test_dirs {
+            let (Some(cur_test), Some(next_test)) = (step(current, dir), step(next, dir)) else {
                 continue;
-            }
-
-            let cur_test = cur_test.as_uvec2();
-            let next_test = next_test.as_uvec2();
+            };
 
             if adjacencies.contains(cur_test)
                 && !visited.contains(&cur_test)
@@ -78,6 +98,7 @@ pub(super) fn longest_path(
                 visited.insert(next_test);
                 path.insert(index, dir);
                 path.insert(index + 2, dir.flip());
+                profiling::count_longest_path_extension();
                 extended = true;
                 break;
             }
@@ -95,17 +116,84 @@ pub(super) fn longest_path(
     Some(path)
 }
 
+/// Moves from `pos` by one cell in the given `direction`, unless that would leave the arena
+/// through its bottom or left edge.
+fn step(pos: UVec2, direction: Direction) -> Option<UVec2> {
+    let next = pos.as_ivec2() + direction.offset();
+
+    if next.x < 0 || next.y < 0 {
+        None
+    } else {
+        Some(next.as_uvec2())
+    }
+}
+
 pub(super) fn astar(
     start: UVec2,
     goal: UVec2,
     direction: Direction,
     adjacencies: &AdjacencyGraph,
+) -> Option<(Vec<Direction>, i32)> {
+    search(start, goal, direction, false, false, |pos, _| {
+        adjacencies.get_neighbors(pos)
+    })
+}
+
+/// Computes the shortest path from `start` to `goal` like [`shortest_path`], but also lets the path
+/// enter cells occupied by the snake once they will have been vacated.
+///
+/// `free_at` holds the number of steps until each cell is free, as computed by
+/// [`time_until_free`](super::analysis::time_until_free). Only the earliest arrival at each cell
+/// is considered, and paths never cross themselves, so paths which circle around to wait for the
+/// tail to move aren't found.
+pub(super) fn timed_shortest_path(
+    start: UVec2,
+    goal: UVec2,
+    direction: Direction,
+    size: UVec2,
+    free_at: &[usize],
+) -> Option<Vec<Direction>> {
+    let neighbors = |pos: UVec2, cost: i32| {
+        // The cell is entered on the step after `cost`, before the tail moves on that step.
+        let arrival = cost as usize + 1;
+
+        DIRECTIONS
+            .into_iter()
+            .filter_map(|dir| {
+                let next = step(pos, dir)?;
+                let free = next.cmplt(size).all() && free_at[(next.y * size.x + next.x) as usize] < arrival;
+                free.then_some((next, dir))
+            })
+            .collect()
+    };
+
+    search(start, goal, direction, true, false, neighbors).map(|v| v.0)
+}
+
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Right, Direction::Left];
+
+/// A* search from `start` to `goal`, where `neighbors` gives the cells reachable from a cell
+/// after the path to it has the given cost.
+///
+/// If `avoid_path` is set, cells already on the path to a cell are never among its successors,
+/// since the snake itself will be occupying them.
+///
+/// If `ordered` is set, the node discovered first is expanded first among equally promising nodes,
+/// so the order of `neighbors` decides between equally short paths.
+fn search(
+    start: UVec2,
+    goal: UVec2,
+    direction: Direction,
+    avoid_path: bool,
+    ordered: bool,
+    neighbors: impl Fn(UVec2, i32) -> SmallVec<[(UVec2, Direction); 4]>,
 ) -> Option<(Vec<Direction>, i32)> {
     let mut open_set = BinaryHeap::new();
     open_set.push(CostHolder {
         estimated_cost: 0,
         cost: 0,
         index: 0,
+        discovered: 0,
     });
 
     let mut parents: FxIndexMap<(UVec2, Direction), (usize, i32)> = FxIndexMap::default();
@@ -123,10 +211,15 @@ pub(super) fn astar(
                 continue;
             }
 
-            adjacencies.get_neighbors(node.0)
+            profiling::count_astar_expansion();
+            neighbors(node.0, cost)
         };
 
         for successor in successors {
+            if avoid_path && on_path(&parents, index, successor.0) {
+                continue;
+            }
+
             let new_cost = cost + 1;
             let h;
             let n;
@@ -152,6 +245,7 @@ pub(super) fn astar(
                 estimated_cost: new_cost + h,
                 cost: new_cost,
                 index: n,
+                discovered: if ordered { n } else { 0 },
             });
         }
     }
@@ -159,6 +253,19 @@ pub(super) fn astar(
     None
 }
 
+/// Checks whether `pos` is on the path leading to the node at index `i`.
+fn on_path(parents: &FxIndexMap<(UVec2, Direction), (usize, i32)>, mut i: usize, pos: UVec2) -> bool {
+    while let Some((node, &(parent, _))) = parents.get_index(i) {
+        if node.0 == pos {
+            return true;
+        }
+
+        i = parent;
+    }
+
+    false
+}
+
 fn reconstruct_path(parents: &FxIndexMap<(UVec2, Direction), (usize, i32)>, mut i: usize) -> Vec<Direction> {
     let path = std::iter::from_fn(|| {
         parents.get_index(i).map(|(node, value)| {
@@ -176,11 +283,16 @@ struct CostHolder<C> {
     estimated_cost: C,
     cost: C,
     index: usize,
+    /// Breaks ties between nodes with the same costs, with the lower one expanded first. Left at 0
+    /// unless the search is ordered, so that the heap decides between them instead.
+    discovered: usize,
 }
 
 impl<C: PartialEq> PartialEq for CostHolder<C> {
     fn eq(&self, other: &Self) -> bool {
-        self.estimated_cost.eq(&other.estimated_cost) && self.cost.eq(&other.cost)
+        self.estimated_cost.eq(&other.estimated_cost)
+            && self.cost.eq(&other.cost)
+            && self.discovered == other.discovered
     }
 }
 
@@ -189,7 +301,7 @@ impl<C: PartialEq> Eq for CostHolder<C> {}
 impl<C: Ord> Ord for CostHolder<C> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         match other.estimated_cost.cmp(&self.estimated_cost) {
-            std::cmp::Ordering::Equal => self.cost.cmp(&other.cost),
+            std::cmp::Ordering::Equal => self.cost.cmp(&other.cost).then(other.discovered.cmp(&self.discovered)),
             s => s,
         }
     }
@@ -207,3 +319,228 @@ type FxIndexMap<K, V> = indexmap::IndexMap<K, V, std::hash::BuildHasherDefault<r
 fn heuristic(pos: UVec2, goal: UVec2) -> i32 {
     (goal.x as i32 - pos.x as i32).abs() + (goal.y as i32 - pos.y as i32).abs()
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        game::GameOutcome,
+        headless::HeadlessGame,
+        settings::Settings,
+        solver::{analysis, astar::AstarSolver, Solver},
+    };
+
+    /// Generates an arena of random size with a random set of cells removed from the graph,
+    /// returning the graph along with a random start and goal node.
+    fn random_graph(rng: &mut StdRng) -> (AdjacencyGraph, UVec2, UVec2) {
+        let size = UVec2::new(rng.gen_range(1..16), rng.gen_range(1..16));
+        let mut adjacencies = AdjacencyGraph::grid(size);
+        let density = rng.gen_range(0.0..0.5);
+
+        for x in 0..size.x {
+            for y in 0..size.y {
+                if rng.gen_bool(density) {
+                    adjacencies.remove(UVec2::new(x, y));
+                }
+            }
+        }
+
+        let start = UVec2::new(rng.gen_range(0..size.x), rng.gen_range(0..size.y));
+        let goal = UVec2::new(rng.gen_range(0..size.x), rng.gen_range(0..size.y));
+        adjacencies.insert(start);
+        adjacencies.insert(goal);
+
+        (adjacencies, start, goal)
+    }
+
+    /// Walks `path` from `start`, checking that it only moves between neighboring nodes of the
+    /// graph without revisiting a cell, and returns the cell it ends on.
+    fn walk(start: UVec2, path: &[Direction], adjacencies: &AdjacencyGraph) -> UVec2 {
+        let mut visited = HashSet::new();
+        let mut current = start;
+        visited.insert(current);
+
+        for &dir in path {
+            assert!(
+                adjacencies.get_directions(current).contains(dir.into()),
+                "path leaves the graph at {current} towards {dir:?}"
+            );
+
+            current = current + dir;
+            assert!(adjacencies.contains(current), "path enters {current}, which isn't free");
+            assert!(visited.insert(current), "path revisits {current}");
+        }
+
+        current
+    }
+
+    #[test]
+    fn fuzz_astar() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..5000 {
+            let (adjacencies, start, goal) = random_graph(&mut rng);
+            let direction = DIRECTIONS[rng.gen_range(0..4)];
+
+            if let Some((path, cost)) = astar(start, goal, direction, &adjacencies) {
+                assert_eq!(path.len() as i32, cost);
+                assert_eq!(walk(start, &path, &adjacencies), goal);
+                assert!(cost >= heuristic(start, goal));
+            }
+        }
+    }
+
+    #[test]
+    fn ties_follow_direction_order() {
+        let adjacencies = AdjacencyGraph::grid(UVec2::new(5, 5));
+        let (start, goal) = (UVec2::new(0, 0), UVec2::new(3, 3));
+
+        for first in [Direction::Up, Direction::Right] {
+            let order = [first, first.flip(), Direction::Left, Direction::Down];
+            let path = shortest_path_ordered(start, goal, Direction::Right, &adjacencies, &order).unwrap();
+
+            assert_eq!(path.len(), 6);
+            assert_eq!(path[0], first);
+            assert_eq!(walk(start, &path, &adjacencies), goal);
+        }
+    }
+
+    #[test]
+    fn fuzz_longest_path() {
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for _ in 0..5000 {
+            let (adjacencies, start, goal) = random_graph(&mut rng);
+            let direction = DIRECTIONS[rng.gen_range(0..4)];
+
+            let Some(shortest) = shortest_path(start, goal, direction, &adjacencies) else {
+                continue;
+            };
+
+            match longest_path(start, &adjacencies, shortest.clone()) {
+                Some(longest) => {
+                    assert!(longest.len() >= shortest.len());
+                    assert_eq!(walk(start, &longest, &adjacencies), goal);
+                },
+                None => assert!(shortest.is_empty()),
+            }
+        }
+    }
+
+    #[test]
+    fn fuzz_longest_path_from_random_walks() {
+        let mut rng = StdRng::seed_from_u64(2);
+
+        for _ in 0..5000 {
+            let (adjacencies, start, _) = random_graph(&mut rng);
+
+            // Take a self-avoiding random walk, occasionally stepping somewhere invalid.
+            let mut visited = HashSet::new();
+            let mut current = start;
+            let mut path = Vec::new();
+            let mut valid = true;
+            visited.insert(current);
+
+            for _ in 0..rng.gen_range(1..32) {
+                let dir = DIRECTIONS[rng.gen_range(0..4)];
+                let Some(next) = step(current, dir) else { continue };
+
+                if !adjacencies.get_directions(current).contains(dir.into()) || visited.contains(&next) {
+                    if rng.gen_bool(0.05) {
+                        valid = false;
+                        path.push(dir);
+                        break;
+                    }
+
+                    continue;
+                }
+
+                visited.insert(next);
+                path.push(dir);
+                current = next;
+            }
+
+            match longest_path(start, &adjacencies, path.clone()) {
+                Some(longest) => {
+                    assert!(valid, "accepted invalid path {path:?} from {start}");
+                    assert!(longest.len() >= path.len());
+                    assert_eq!(walk(start, &longest, &adjacencies), current);
+                },
+                None => assert!(!valid || path.is_empty(), "rejected valid path {path:?} from {start}"),
+            }
+        }
+    }
+
+    #[test]
+    fn longest_path_rejects_paths_leaving_the_graph() {
+        let mut adjacencies = AdjacencyGraph::grid(UVec2::new(4, 4));
+        adjacencies.remove(UVec2::new(1, 0));
+
+        assert!(longest_path(UVec2::ZERO, &adjacencies, vec![Direction::Left]).is_none());
+        assert!(longest_path(UVec2::ZERO, &adjacencies, vec![Direction::Down]).is_none());
+        assert!(longest_path(UVec2::ZERO, &adjacencies, vec![Direction::Right]).is_none());
+        assert!(longest_path(
+            UVec2::ZERO,
+            &adjacencies,
+            vec![Direction::Up, Direction::Up, Direction::Down]
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn timed_paths_avoid_the_snake() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut through_body = 0;
+
+        for seed in 0..300 {
+            let settings = Settings {
+                arena_size: UVec2::new(8, 8),
+                seed: Some(seed),
+                ..Default::default()
+            };
+            let mut game = HeadlessGame::new(settings, Solver::new("astar", AstarSolver::default()));
+
+            // Play a while to get a longer snake.
+            let steps = rng.gen_range(0..200);
+            if (0..steps).any(|_| game.step().is_some()) {
+                continue;
+            }
+
+            let arena = game.arena();
+            let snake = game.snake();
+            let goal = UVec2::new(rng.gen_range(0..8), rng.gen_range(0..8));
+            let free_at = analysis::time_until_free(arena, snake);
+
+            let Some(path) = timed_shortest_path(arena.head, goal, snake.direction, arena.size, &free_at) else {
+                continue;
+            };
+
+            if shortest_path(arena.head, goal, snake.direction, &arena.adjacencies).is_none() {
+                through_body += 1;
+            }
+
+            // The cells are only guaranteed to free up in time if the snake doesn't eat.
+            let length = snake.length;
+
+            let interrupted = path.into_iter().any(|dir| {
+                let outcome = game.step_towards(dir);
+                assert_ne!(
+                    outcome,
+                    Some(GameOutcome::Lost),
+                    "ran into the snake\n{:?}",
+                    game.arena()
+                );
+
+                outcome.is_some() || game.snake().length != length
+            });
+
+            if !interrupted {
+                assert_eq!(game.arena().head, goal);
+            }
+        }
+
+        assert!(through_body > 0);
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    io,
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    time::Duration,
+};
+
+use bevy::{
+    log::{error, info, warn},
+    reflect::Reflect,
+};
+
+use crate::{
+    arena::{Arena, Direction},
+    protocol::{Connection, Message},
+    snake::Snake,
+};
+
+use super::SolveMethod;
+
+/// Solver which listens for a bot connecting over TCP and asks it for every move, using the
+/// line-based JSON protocol in [`protocol`](crate::protocol).
+///
+/// Only one bot plays at a time, with a newly connected bot taking over from the previous one, so
+/// bots can't play each other in a [`Tournament`](crate::tournament::Tournament). Bots have until
+/// the next step is due to reply. While no bot is connected, or the bot doesn't reply in time, the
+/// snake keeps moving in its last direction.
+///
+/// Waiting for the reply blocks the frame the step happens in, so the window neither redraws nor
+/// takes input until the bot replies or its time runs out. Slow bots make the game stutter.
+#[derive(Reflect, Default)]
+pub struct RemoteSolver {
+    pub settings: RemoteSettings,
+    #[reflect(ignore)]
+    listener: Option<(String, TcpListener)>,
+    #[reflect(ignore)]
+    bot: Option<Bot>,
+    #[reflect(ignore)]
+    start: Option<Message>,
+    #[reflect(ignore)]
+    step_interval: Option<Duration>,
+}
+
+/// Settings of the [`RemoteSolver`]. The address takes effect once the next game starts.
+#[derive(Reflect, Debug, Clone)]
+pub struct RemoteSettings {
+    /// The address to listen on for bots.
+    pub address: String,
+    /// How long to wait for each move when there is no time between steps, such as when the game
+    /// steps every frame, in milliseconds.
+    pub timeout_ms: u64,
+}
+
+impl Default for RemoteSettings {
+    fn default() -> Self {
+        Self {
+            address: String::from("127.0.0.1:7878"),
+            timeout_ms: 100,
+        }
+    }
+}
+
+impl RemoteSolver {
+    /// A remote solver listening on `address`, as in [`RemoteSettings::address`].
+    pub fn new(address: impl Into<String>) -> RemoteSolver {
+        Self {
+            settings: RemoteSettings {
+                address: address.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// The address bots can connect to, once the solver has started listening.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.listener.as_ref()?.1.local_addr().ok()
+    }
+
+    /// How long to wait for the bot to reply, which is until the next step is due.
+    fn timeout(&self) -> Duration {
+        self.step_interval
+            .unwrap_or_else(|| Duration::from_millis(self.settings.timeout_ms))
+    }
+
+    /// Starts listening on the configured address, unless already listening there.
+    fn listen(&mut self) -> io::Result<()> {
+        if self
+            .listener
+            .as_ref()
+            .is_some_and(|(address, _)| *address == self.settings.address)
+        {
+            return Ok(());
+        }
+
+        self.listener = None;
+        self.bot = None;
+
+        let listener = TcpListener::bind(&self.settings.address)?;
+        listener.set_nonblocking(true)?;
+        info!("waiting for bots on {}", listener.local_addr()?);
+        self.listener = Some((self.settings.address.clone(), listener));
+
+        Ok(())
+    }
+
+    /// Takes on the most recently connected bot, if any bots have connected since the last call.
+    fn accept(&mut self) -> io::Result<()> {
+        let Some((_, listener)) = &self.listener else {
+            return Ok(());
+        };
+
+        loop {
+            let (stream, address) = match listener.accept() {
+                Ok(accepted) => accepted,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e),
+            };
+
+            info!("bot connected from {}", address);
+            stream.set_nonblocking(false)?;
+            stream.set_nodelay(true)?;
+
+            let mut bot = Bot {
+                connection: Connection::new(stream.try_clone()?, stream.try_clone()?),
+                stream,
+            };
+
+            if let Some(start) = &self.start {
+                bot.connection.send(start)?;
+            }
+
+            self.bot = Some(bot);
+        }
+    }
+}
+
+impl SolveMethod for RemoteSolver {
+    fn initialize(&mut self, _snake: &Snake, arena: &Arena, seed: u64) {
+        let start = Message::start(arena, seed);
+
+        if let Err(e) = self.listen() {
+            error!("failed to listen on {}: {}", self.settings.address, e);
+        }
+
+        if let Some(bot) = &mut self.bot {
+            if let Err(e) = bot.connection.send(&start) {
+                warn!("bot disconnected: {}", e);
+                self.bot = None;
+            }
+        }
+
+        self.start = Some(start);
+    }
+
+    fn get_direction(&mut self, snake: &Snake, arena: &Arena) -> Direction {
+        if let Err(e) = self.accept() {
+            error!("failed to accept bot: {}", e);
+        }
+
+        let timeout = self.timeout();
+        let Some(bot) = &mut self.bot else {
+            return snake.direction;
+        };
+
+        match bot.connection.request(&Message::state(snake, arena), timeout) {
+            Ok(Some(reply)) => reply.direction,
+            Ok(None) => {
+                warn!("bot didn't reply within {:?}", timeout);
+                snake.direction
+            },
+            Err(e) => {
+                warn!("bot disconnected: {}", e);
+                self.bot = None;
+                snake.direction
+            },
+        }
+    }
+
+    fn set_step_interval(&mut self, interval: Option<Duration>) {
+        self.step_interval = interval;
+    }
+
+    fn settings_mut(&mut self) -> Option<&mut dyn Reflect> {
+        Some(&mut self.settings)
+    }
+}
+
+/// A connected bot, which is disconnected when dropped.
+struct Bot {
+    stream: TcpStream,
+    connection: Connection,
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::BufReader, net::TcpStream, thread};
+
+    use bevy::math::UVec2;
+
+    use super::*;
+    use crate::{
+        headless::HeadlessGame,
+        protocol::{self, Reply},
+        settings::Settings,
+        solver::Solver,
+    };
+
+    fn game() -> HeadlessGame {
+        let settings = Settings {
+            arena_size: UVec2::new(8, 8),
+            seed: Some(0),
+            ..Default::default()
+        };
+
+        HeadlessGame::new(settings, Solver::new("remote", RemoteSolver::new("127.0.0.1:0")))
+    }
+
+    #[test]
+    fn connected_bots_steer_the_snake() {
+        let mut game = game();
+        let address = game
+            .solver()
+            .downcast_ref::<RemoteSolver>()
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let stream = TcpStream::connect(address).unwrap();
+
+        let bot = thread::spawn(move || {
+            let mut writer = stream.try_clone().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut messages = Vec::new();
+
+            while let Ok(Some(message)) = protocol::receive::<Message>(&mut reader) {
+                if let Message::State { steps, .. } = message {
+                    protocol::send(
+                        &mut writer,
+                        &Reply {
+                            direction: Direction::Up,
+                            steps,
+                        },
+                    )
+                    .unwrap();
+                }
+
+                messages.push(message);
+            }
+
+            messages
+        });
+
+        game.step();
+        assert_eq!(game.snake().direction, Direction::Up);
+
+        drop(game);
+        let messages = bot.join().unwrap();
+
+        assert!(matches!(
+            messages[0],
+            Message::Start {
+                width: 8,
+                height: 8,
+                seed: 0
+            }
+        ));
+        assert!(matches!(messages[1], Message::State { steps: 0, .. }));
+    }
+
+    #[test]
+    fn snake_moves_straight_without_bots() {
+        let mut game = game();
+        let direction = game.snake().direction;
+        game.step();
+
+        assert_eq!(game.snake().direction, direction);
+    }
+
+    #[test]
+    fn bots_have_until_the_next_step_to_reply() {
+        let mut solver = RemoteSolver::new("127.0.0.1:0");
+        assert_eq!(solver.timeout(), Duration::from_millis(100));
+
+        solver.set_step_interval(Some(Duration::from_millis(40)));
+        assert_eq!(solver.timeout(), Duration::from_millis(40));
+    }
+}
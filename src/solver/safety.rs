@@ -0,0 +1,134 @@
+use bevy::{math::IVec2, reflect::Reflect};
+
+use crate::{
+    arena::{Arena, Direction},
+    simulation::Simulation,
+    snake::Snake,
+};
+
+use super::analysis::Components;
+
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+/// Checks which solvers can opt into to avoid moves which trap the snake.
+///
+/// Each check looks at the arena as it will be after a single move, so none of them can rule out
+/// losing further ahead.
+#[derive(Reflect, Debug, Clone, Copy, Default)]
+pub struct Safety {
+    /// Rejects moves after which fewer free cells can be reached than the snake is long.
+    pub check_area: bool,
+    /// Rejects moves after which the head can no longer reach the tail of the snake.
+    pub check_tail: bool,
+}
+
+impl Safety {
+    /// Both checks enabled.
+    pub const ALL: Safety = Safety {
+        check_area: true,
+        check_tail: true,
+    };
+
+    /// Picks the direction to move in, taking `preferred` if it passes every enabled check.
+    ///
+    /// Otherwise, the move leaving the most reachable free cells is taken, preferring moves which
+    /// pass every check. With every check disabled `preferred` is returned as is.
+    pub fn choose(&self, snake: &Snake, arena: &Arena, preferred: Option<Direction>) -> Option<Direction> {
+        if !self.check_area && !self.check_tail {
+            return preferred;
+        }
+
+        if let Some(m) = preferred.and_then(|dir| Move::new(snake, arena, dir)) {
+            if self.is_safe(&m) {
+                return preferred;
+            }
+        }
+
+        DIRECTIONS
+            .into_iter()
+            .filter_map(|dir| Move::new(snake, arena, dir))
+            .max_by_key(|m| (self.is_safe(m), m.area))
+            .map(|m| m.direction)
+    }
+
+    fn is_safe(&self, m: &Move) -> bool {
+        (!self.check_area || m.area >= m.length) && (!self.check_tail || m.tail_reachable)
+    }
+}
+
+/// The outcome of moving the snake one cell in `direction`.
+struct Move {
+    direction: Direction,
+    /// The length of the snake after the move.
+    length: usize,
+    /// The number of free cells the head can reach after the move, including itself.
+    area: usize,
+    tail_reachable: bool,
+}
+
+impl Move {
+    /// Simulates a move in `direction`, returning [`None`] if it runs into a wall or the snake.
+    fn new(snake: &Snake, arena: &Arena, direction: Direction) -> Option<Move> {
+        let mut simulation = Simulation::new(snake, arena);
+        simulation.step(direction)?;
+
+        let head = simulation.head();
+        let tail = simulation.tail();
+        let length = simulation.length();
+        let components = Components::new(simulation.adjacencies());
+
+        let tail_reachable = DIRECTIONS.into_iter().any(|dir| {
+            let neighbor = tail.as_ivec2() + dir.offset();
+
+            neighbor.cmpge(IVec2::ZERO).all()
+                && neighbor.cmplt(arena.size.as_ivec2()).all()
+                && components.label(neighbor.as_uvec2()) == components.label(head)
+        });
+
+        Some(Move {
+            direction,
+            length,
+            area: components.area(head),
+            tail_reachable,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::UVec2;
+
+    use super::*;
+    use crate::{
+        headless::HeadlessGame,
+        settings::Settings,
+        solver::{astar::AstarSolver, Solver},
+    };
+
+    fn mean_length(safety: Safety) -> f64 {
+        let lengths = (0..20).map(|seed| {
+            let settings = Settings {
+                arena_size: UVec2::new(8, 8),
+                seed: Some(seed),
+                ..Default::default()
+            };
+            let mut solver = AstarSolver::default();
+            solver.settings.safety = safety;
+
+            let mut game = HeadlessGame::new(settings, Solver::new("astar", solver));
+            while game.step().is_none() {}
+
+            game.snake().length
+        });
+
+        lengths.sum::<usize>() as f64 / 20.0
+    }
+
+    #[test]
+    fn safety_lets_astar_grow_longer() {
+        let unsafe_length = mean_length(Safety::default());
+        let safe_length = mean_length(Safety::ALL);
+
+        assert!(safe_length > unsafe_length, "{safe_length} <= {unsafe_length}");
+    }
+}
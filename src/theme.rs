@@ -0,0 +1,426 @@
+//! Themes for drawing the snake and food, either as the flat rectangles of
+//! [`update_cell`](crate::arena::update_cell) or as sprites from a texture atlas.
+//!
+//! An atlas is a single row of square tiles in the order of [`Tile`]. Each tile is drawn as shown
+//! for a snake facing up, and rotated for the other directions.
+
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use bevy::{
+    color::ColorToPacked,
+    image::ImageSampler,
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+    utils::HashMap,
+};
+use image::{Rgba, RgbaImage};
+
+use crate::{
+    arena::{self, Arena, Cell, Direction},
+    cell::{self, DrawCell, DrawCellTransform, ForegroundCell},
+    game::Draw,
+    settings::{ColorSettings, Settings},
+    snake::Snake,
+};
+
+/// The tiles of an atlas, in the order they appear from left to right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    /// The head, facing up.
+    Head,
+    /// A body segment joined to the segments above and below it.
+    Straight,
+    /// A body segment joined to the segments above and to the right of it.
+    Corner,
+    /// The end of the tail, joined to the segment above it.
+    Tail,
+    Food,
+}
+
+impl Tile {
+    pub const COUNT: u32 = 5;
+}
+
+/// How a theme draws the snake and food.
+#[derive(Clone)]
+pub enum Style {
+    /// Flat coloured rectangles, joined up between neighboring segments.
+    Flat,
+    /// Sprites from a texture atlas.
+    Atlas {
+        image: Handle<Image>,
+        layout: Handle<TextureAtlasLayout>,
+    },
+}
+
+/// The theme the snake and food are currently drawn with.
+#[derive(Resource, Clone)]
+pub struct Theme {
+    name: String,
+    style: Style,
+}
+
+impl Theme {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn style(&self) -> &Style {
+        &self.style
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            name: String::from("flat"),
+            style: Style::Flat,
+        }
+    }
+}
+
+/// Every theme that can be picked by name, in the order they were added.
+#[derive(Resource)]
+pub struct Themes {
+    themes: Vec<Theme>,
+}
+
+impl Themes {
+    /// Adds a theme, replacing any earlier theme with the same name.
+    pub fn add(&mut self, name: impl Into<String>, style: Style) -> &mut Self {
+        let theme = Theme {
+            name: name.into(),
+            style,
+        };
+
+        match self.themes.iter_mut().find(|t| t.name == theme.name) {
+            Some(existing) => *existing = theme,
+            None => self.themes.push(theme),
+        }
+
+        self
+    }
+
+    /// Adds a theme drawn from the atlas `pixels`, which must be [`Tile::COUNT`] square tiles wide
+    /// and one tile high.
+    pub fn add_atlas(
+        &mut self,
+        name: impl Into<String>,
+        pixels: RgbaImage,
+        images: &mut Assets<Image>,
+        layouts: &mut Assets<TextureAtlasLayout>,
+    ) -> &mut Self {
+        let tile_size = UVec2::splat(pixels.height());
+        let layout = TextureAtlasLayout::from_grid(tile_size, Tile::COUNT, 1, None, None);
+        let mut image = Image::new(
+            Extent3d {
+                width: pixels.width(),
+                height: pixels.height(),
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            pixels.into_raw(),
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::RENDER_WORLD,
+        );
+        image.sampler = ImageSampler::nearest();
+
+        self.add(
+            name,
+            Style::Atlas {
+                image: images.add(image),
+                layout: layouts.add(layout),
+            },
+        )
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.themes.iter().map(|t| t.name.as_str())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Theme> {
+        self.themes.iter().find(|t| t.name == name)
+    }
+}
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Theme>()
+            .add_systems(Startup, setup_themes)
+            .add_systems(
+                Draw,
+                (
+                    update_themed_cells.before(cell::update_cell_transform),
+                    hide_flat_cells.after(arena::update_cell),
+                ),
+            );
+    }
+}
+
+/// Sets up the flat theme, the built-in `blocks` atlas theme, and an atlas theme for every PNG in
+/// [`Settings::themes_path`], named after its file.
+pub fn setup_themes(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut images: ResMut<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let mut themes = Themes { themes: Vec::new() };
+
+    themes
+        .add("flat", Style::Flat)
+        .add_atlas("blocks", blocks_atlas(&settings.colors, 16), &mut images, &mut layouts);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    for (name, pixels) in load_atlases(&settings.themes_path) {
+        themes.add_atlas(name, pixels, &mut images, &mut layouts);
+    }
+
+    commands.insert_resource(themes);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_atlases(path: &std::path::Path) -> Vec<(String, RgbaImage)> {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+        Err(e) => {
+            warn!("failed to read themes from {}: {}", path.display(), e);
+            return Vec::new();
+        },
+    };
+
+    let mut atlases = Vec::new();
+
+    for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+        let (Some(name), Some("png")) = (path.file_stem(), path.extension().and_then(|ext| ext.to_str())) else {
+            continue;
+        };
+
+        match image::open(&path) {
+            Ok(pixels) if pixels.width() == pixels.height() * Tile::COUNT => {
+                atlases.push((name.to_string_lossy().into_owned(), pixels.into_rgba8()));
+            },
+            Ok(_) => warn!(
+                "{} should be {} square tiles wide and one tile high",
+                path.display(),
+                Tile::COUNT
+            ),
+            Err(e) => warn!("failed to load theme {}: {}", path.display(), e),
+        }
+    }
+
+    atlases.sort_by(|a, b| a.0.cmp(&b.0));
+    atlases
+}
+
+/// Draws the tiles of a simple atlas theme in the given `colors`, `size` pixels wide each.
+pub fn blocks_atlas(colors: &ColorSettings, size: u32) -> RgbaImage {
+    let mut pixels = RgbaImage::new(size * Tile::COUNT, size);
+    let pixel = |color: Color| Rgba(color.to_srgba().to_u8_array());
+    let (head, body, food) = (pixel(colors.snake_head), pixel(colors.snake_tail), pixel(colors.food));
+    let eyes = pixel(colors.background_dark);
+
+    // Fills the rectangle from `min` to `max` within `tile`, measured in sixteenths of a tile from
+    // its bottom left corner.
+    let mut fill = |tile: Tile, min: UVec2, max: UVec2, color: Rgba<u8>| {
+        for y in min.y * size / 16..max.y * size / 16 {
+            for x in min.x * size / 16..max.x * size / 16 {
+                pixels.put_pixel(tile as u32 * size + x, size - 1 - y, color);
+            }
+        }
+    };
+
+    fill(Tile::Head, UVec2::new(3, 0), UVec2::new(13, 11), head);
+    fill(Tile::Head, UVec2::new(5, 11), UVec2::new(11, 13), head);
+    fill(Tile::Head, UVec2::new(5, 7), UVec2::new(7, 9), eyes);
+    fill(Tile::Head, UVec2::new(9, 7), UVec2::new(11, 9), eyes);
+
+    fill(Tile::Straight, UVec2::new(3, 0), UVec2::new(13, 16), body);
+
+    fill(Tile::Corner, UVec2::new(3, 3), UVec2::new(13, 16), body);
+    fill(Tile::Corner, UVec2::new(13, 3), UVec2::new(16, 13), body);
+
+    fill(Tile::Tail, UVec2::new(3, 8), UVec2::new(13, 16), body);
+    fill(Tile::Tail, UVec2::new(5, 4), UVec2::new(11, 8), body);
+    fill(Tile::Tail, UVec2::new(7, 2), UVec2::new(9, 4), body);
+
+    fill(Tile::Food, UVec2::new(5, 4), UVec2::new(11, 12), food);
+    fill(Tile::Food, UVec2::new(4, 5), UVec2::new(12, 11), food);
+
+    pixels
+}
+
+/// A sprite drawing a snake or food cell with an atlas theme.
+#[derive(Component)]
+pub struct ThemedCell;
+
+/// Draws the snake and food with the current atlas theme, keeping one sprite for every cell they
+/// cover. The sprites are laid out along with the other cells by
+/// [`update_cell_transform`](crate::cell::update_cell_transform).
+pub fn update_themed_cells(
+    mut commands: Commands,
+    arena: Res<Arena>,
+    snake: Res<Snake>,
+    theme: Res<Theme>,
+    mut sprites: Query<(&mut Sprite, &mut Transform), With<ThemedCell>>,
+    mut positions: Local<HashMap<UVec2, Entity>>,
+) {
+    if theme.is_changed() {
+        for (_, entity) in positions.drain() {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    let Style::Atlas { image, layout } = theme.style() else {
+        return;
+    };
+
+    positions.retain(|&pos, &mut entity| {
+        let keep = arena.get_cell_unchecked(pos) != Cell::None;
+
+        if !keep {
+            commands.entity(entity).despawn();
+        }
+
+        keep
+    });
+
+    for (pos, cell) in arena.cells() {
+        let Some((tile, direction)) = tile(&arena, &snake, pos, cell) else {
+            continue;
+        };
+
+        let rotation = Quat::from_rotation_z(angle(direction));
+
+        if let Some((mut sprite, mut transform)) = positions.get(&pos).and_then(|&e| sprites.get_mut(e).ok()) {
+            if let Some(atlas) = &mut sprite.texture_atlas {
+                atlas.index = tile as usize;
+            }
+
+            transform.rotation = rotation;
+            continue;
+        }
+
+        let mut sprite = Sprite::from_atlas_image(
+            image.clone(),
+            TextureAtlas {
+                layout: layout.clone(),
+                index: tile as usize,
+            },
+        );
+        sprite.custom_size = Some(Vec2::ONE);
+
+        let entity = commands
+            .spawn((
+                DrawCell { pos },
+                sprite,
+                ForegroundCell { contents: cell },
+                DrawCellTransform {
+                    size: Vec2::ONE,
+                    offset: Vec2::ZERO,
+                },
+                Transform::from_rotation(rotation),
+                ThemedCell,
+            ))
+            .id();
+
+        positions.insert(pos, entity);
+    }
+}
+
+/// Hides the rectangles drawn by [`update_cell`](crate::arena::update_cell) while an atlas theme is
+/// in use.
+pub fn hide_flat_cells(
+    theme: Res<Theme>,
+    mut cells: Query<&mut Visibility, (With<ForegroundCell>, Without<ThemedCell>)>,
+) {
+    let visibility = match theme.style() {
+        Style::Flat => Visibility::Inherited,
+        Style::Atlas { .. } => Visibility::Hidden,
+    };
+
+    for mut cell in &mut cells {
+        cell.set_if_neq(visibility);
+    }
+}
+
+/// The tile drawn for the cell at `pos`, along with the direction its top should face, or [`None`]
+/// if nothing is drawn there.
+fn tile(arena: &Arena, snake: &Snake, pos: UVec2, cell: Cell) -> Option<(Tile, Direction)> {
+    let tile = match cell {
+        Cell::None => return None,
+        Cell::Food => (Tile::Food, Direction::Up),
+        Cell::SnakeHead => (Tile::Head, snake.direction),
+        Cell::SnakeTail { .. } => {
+            let dirs = arena.segment_neighbors(pos);
+
+            match (dirs.up(), dirs.down(), dirs.left(), dirs.right()) {
+                (true, true, _, _) => (Tile::Straight, Direction::Up),
+                (_, _, true, true) => (Tile::Straight, Direction::Right),
+                // Corners are named after the first of their two directions going clockwise.
+                (true, _, _, true) => (Tile::Corner, Direction::Up),
+                (_, true, _, true) => (Tile::Corner, Direction::Right),
+                (_, true, true, _) => (Tile::Corner, Direction::Down),
+                (true, _, true, _) => (Tile::Corner, Direction::Left),
+                (true, _, _, _) => (Tile::Tail, Direction::Up),
+                (_, true, _, _) => (Tile::Tail, Direction::Down),
+                (_, _, true, _) => (Tile::Tail, Direction::Left),
+                (_, _, _, true) => (Tile::Tail, Direction::Right),
+                _ => return None,
+            }
+        },
+    };
+
+    Some(tile)
+}
+
+/// The rotation turning a tile facing up to face `direction`, counterclockwise.
+fn angle(direction: Direction) -> f32 {
+    match direction {
+        Direction::Up => 0.0,
+        Direction::Left => FRAC_PI_2,
+        Direction::Down => PI,
+        Direction::Right => -FRAC_PI_2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{headless::HeadlessGame, solver::Solver};
+
+    #[test]
+    fn tiles_follow_the_snake() {
+        let settings = Settings {
+            arena_size: UVec2::new(8, 4),
+            seed: Some(0),
+            ..Default::default()
+        };
+        let mut game = HeadlessGame::new(settings, Solver::default());
+        let at = |game: &HeadlessGame, x, y| {
+            let pos = UVec2::new(x, y);
+            tile(game.arena(), game.snake(), pos, game.arena().get_cell_unchecked(pos))
+        };
+
+        // The snake starts out facing left.
+        assert_eq!(at(&game, 3, 2), Some((Tile::Head, Direction::Left)));
+        assert_eq!(at(&game, 4, 2), Some((Tile::Straight, Direction::Right)));
+        assert_eq!(at(&game, 5, 2), Some((Tile::Tail, Direction::Left)));
+        assert_eq!(at(&game, 0, 0), None);
+
+        game.step_towards(Direction::Up);
+
+        assert_eq!(at(&game, 3, 3), Some((Tile::Head, Direction::Up)));
+        assert_eq!(at(&game, 3, 2), Some((Tile::Corner, Direction::Up)));
+        assert_eq!(at(&game, 4, 2), Some((Tile::Tail, Direction::Left)));
+
+        let atlas = blocks_atlas(&ColorSettings::default(), 16);
+        assert_eq!(atlas.dimensions(), (16 * Tile::COUNT, 16));
+    }
+}
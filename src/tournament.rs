@@ -0,0 +1,259 @@
+//! Round-robin tournaments between solvers, rated with Elo.
+//!
+//! Every solver plays the same seeds, and for every seed each pair of solvers is compared as a
+//! match: winning the game beats losing it, then a longer snake beats a shorter one, then fewer
+//! steps beat more. Solvers never share an arena, since the game only has room for one snake.
+
+use std::{cmp::Ordering, fmt, io};
+
+use bevy::prelude::*;
+
+use crate::{
+    game::GameOutcome,
+    headless::HeadlessGame,
+    settings::Settings,
+    solver::{hamilton::HamiltonSolver, SolverRegistry},
+};
+
+/// The rating every solver starts out with.
+const INITIAL_RATING: f64 = 1500.0;
+
+/// How far a single match can move a rating.
+const K_FACTOR: f64 = 16.0;
+
+/// The outcome of a single game played by one solver.
+#[derive(Debug, Clone)]
+pub struct GameResult {
+    pub solver: String,
+    pub seed: u64,
+    pub outcome: GameOutcome,
+    pub length: usize,
+    pub steps: usize,
+}
+
+impl GameResult {
+    /// Compares the results of two solvers on the same seed, with the better result being greater.
+    fn compare(&self, other: &GameResult) -> Ordering {
+        let key = |r: &GameResult| (r.outcome == GameOutcome::Won, r.length);
+
+        key(self).cmp(&key(other)).then(other.steps.cmp(&self.steps))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Standing {
+    pub solver: String,
+    pub rating: f64,
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+/// The results of a tournament, along with the standings ordered from best to worst rating.
+#[derive(Debug, Clone)]
+pub struct Tournament {
+    pub results: Vec<GameResult>,
+    pub standings: Vec<Standing>,
+}
+
+impl Tournament {
+    /// Plays every solver in `solvers` from the `registry` on `seeds` seeds of an arena of the
+    /// given `size`.
+    ///
+    /// Returns an error naming the first solver missing from the registry.
+    pub fn run(registry: &SolverRegistry, solvers: &[&str], size: UVec2, seeds: u64) -> Result<Tournament, String> {
+        let mut results = Vec::new();
+        let mut standings = Vec::new();
+
+        for &name in solvers {
+            registry
+                .create(name)
+                .ok_or_else(|| format!("unknown solver `{}`", name))?;
+
+            standings.push(Standing {
+                solver: name.to_string(),
+                rating: INITIAL_RATING,
+                wins: 0,
+                draws: 0,
+                losses: 0,
+            });
+        }
+
+        for seed in 0..seeds {
+            let games = solvers
+                .iter()
+                .map(|&name| play(registry, name, size, seed))
+                .collect::<Vec<_>>();
+
+            for i in 0..games.len() {
+                for j in i + 1..games.len() {
+                    record(&mut standings, i, j, games[i].compare(&games[j]));
+                }
+            }
+
+            results.extend(games);
+        }
+
+        standings.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+
+        Ok(Self { results, standings })
+    }
+
+    /// Plays every solver in the `registry`, which only holds the built-in solvers unless more are
+    /// registered, as in [`Tournament::run`].
+    ///
+    /// The Hamilton solver is left out on arenas without a cycle for it to follow, see
+    /// [`HamiltonSolver::supports`].
+    pub fn run_all(registry: &SolverRegistry, size: UVec2, seeds: u64) -> Tournament {
+        let solvers = registry
+            .names()
+            .filter(|&name| name != "hamilton" || HamiltonSolver::supports(size))
+            .collect::<Vec<_>>();
+
+        Self::run(registry, &solvers, size, seeds).expect("registered solvers exist")
+    }
+
+    /// Writes every game result as CSV, with a header row.
+    pub fn write_csv(&self, mut writer: impl io::Write) -> io::Result<()> {
+        writeln!(writer, "seed,solver,outcome,length,steps")?;
+
+        for result in &self.results {
+            writeln!(
+                writer,
+                "{},{},{:?},{},{}",
+                result.seed, result.solver, result.outcome, result.length, result.steps
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Tournament {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "rank solver     rating  wins draws losses")?;
+
+        for (rank, standing) in self.standings.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>4} {:<10} {:>6.0} {:>5} {:>5} {:>6}",
+                rank + 1,
+                standing.solver,
+                standing.rating,
+                standing.wins,
+                standing.draws,
+                standing.losses
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn play(registry: &SolverRegistry, name: &str, size: UVec2, seed: u64) -> GameResult {
+    let settings = Settings {
+        arena_size: size,
+        seed: Some(seed),
+        ..Default::default()
+    };
+    let mut game = HeadlessGame::new(settings, registry.create(name).unwrap());
+
+    let outcome = loop {
+        if let Some(outcome) = game.step() {
+            break outcome;
+        }
+    };
+
+    GameResult {
+        solver: name.to_string(),
+        seed,
+        outcome,
+        length: game.snake().length,
+        steps: game.snake().steps,
+    }
+}
+
+/// Updates the standings of solvers `i` and `j` after a match, where `ordering` compares the
+/// result of `i` to that of `j`.
+fn record(standings: &mut [Standing], i: usize, j: usize, ordering: Ordering) {
+    let score = match ordering {
+        Ordering::Greater => 1.0,
+        Ordering::Equal => 0.5,
+        Ordering::Less => 0.0,
+    };
+    let expected = 1.0 / (1.0 + 10f64.powf((standings[j].rating - standings[i].rating) / 400.0));
+    let change = K_FACTOR * (score - expected);
+
+    standings[i].rating += change;
+    standings[j].rating -= change;
+
+    match ordering {
+        Ordering::Greater => (standings[i].wins += 1, standings[j].losses += 1),
+        Ordering::Equal => (standings[i].draws += 1, standings[j].draws += 1),
+        Ordering::Less => (standings[i].losses += 1, standings[j].wins += 1),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arena::{Arena, Direction},
+        snake::Snake,
+        solver::SolveMethod,
+    };
+
+    /// Moves straight ahead until it runs into something.
+    #[derive(Reflect, Default)]
+    struct StraightSolver;
+
+    impl SolveMethod for StraightSolver {
+        fn get_direction(&mut self, snake: &Snake, _arena: &Arena) -> Direction {
+            snake.direction
+        }
+    }
+
+    #[test]
+    fn tournaments_rank_solvers() {
+        let mut registry = SolverRegistry::builtin();
+        registry.register::<StraightSolver>("straight");
+
+        let solvers = ["straight", "astar", "hamilton"];
+        let tournament = Tournament::run(&registry, &solvers, UVec2::new(6, 6), 4).unwrap();
+
+        assert_eq!(tournament.results.len(), 12);
+        assert_eq!(tournament.standings.last().unwrap().solver, "straight");
+        assert_eq!(tournament.standings.last().unwrap().losses, 8);
+
+        let total = tournament.standings.iter().map(|s| s.rating).sum::<f64>();
+        assert!((total - 3.0 * INITIAL_RATING).abs() < 1e-6);
+
+        let mut csv = Vec::new();
+        tournament.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+
+        assert_eq!(csv.lines().count(), 13);
+        assert!(csv.lines().nth(1).unwrap().starts_with("0,straight,Lost,"));
+
+        assert!(Tournament::run(&registry, &["missing"], UVec2::new(6, 6), 1).is_err());
+    }
+
+    #[test]
+    fn tournaments_default_to_builtin_solvers() {
+        let tournament = Tournament::run_all(&SolverRegistry::builtin(), UVec2::new(6, 6), 1);
+
+        let mut solvers = tournament
+            .standings
+            .iter()
+            .map(|s| s.solver.as_str())
+            .collect::<Vec<_>>();
+        solvers.sort();
+
+        assert_eq!(solvers, ["astar", "greedy", "hamilton"]);
+        assert_eq!(tournament.results.len(), 3);
+
+        let odd = Tournament::run_all(&SolverRegistry::builtin(), UVec2::new(5, 5), 1);
+        assert!(odd.standings.iter().all(|s| s.solver != "hamilton"));
+        assert_eq!(odd.results.len(), 2);
+    }
+}
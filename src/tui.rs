@@ -0,0 +1,272 @@
+//! Text-mode frontend, which plays the game in a terminal instead of a window, such as on machines
+//! without a display or over SSH.
+
+use std::{
+    io::{self, BufWriter, Write},
+    panic, thread,
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    color::{self, ColorToPacked},
+    math::UVec2,
+};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{self, Print, SetBackgroundColor, SetForegroundColor},
+    terminal::{self, ClearType},
+};
+
+use crate::{
+    arena::{Arena, Cell, Direction, Directions},
+    game::GameOutcome,
+    headless::HeadlessGame,
+    settings::{ColorSettings, Settings},
+    solver::SolverRegistry,
+};
+
+/// The shortest time between steps the player can speed up to.
+const MIN_INTERVAL: Duration = Duration::from_millis(1);
+
+/// The longest time between steps the player can slow down to.
+const MAX_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Plays games in the terminal until the player quits, steering the snake with the arrow keys if
+/// `solver` is [`None`] and with the solver registered under that name otherwise.
+///
+/// A new game on an arena of the given `size` starts whenever the player asks for one. The snake
+/// takes a step every `interval`, which the player can speed up or slow down within
+/// [`MIN_INTERVAL`] and [`MAX_INTERVAL`].
+pub fn run(registry: &SolverRegistry, solver: Option<&str>, size: UVec2, interval: Duration) -> io::Result<()> {
+    let mut interval = interval.clamp(MIN_INTERVAL, MAX_INTERVAL);
+    let colors = ColorSettings::default();
+    let mut out = BufWriter::new(io::stdout());
+    let _terminal = Terminal::enter()?;
+
+    let mut game = new_game(registry, solver, size);
+    let mut turn = None;
+    let mut paused = false;
+    let mut next_step = Instant::now() + interval;
+
+    loop {
+        draw(&mut out, &game, &colors, &status(&game, solver, paused, interval))?;
+
+        if event::poll(next_step.saturating_duration_since(Instant::now()))? {
+            match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                    KeyCode::Char(' ') => paused = !paused,
+                    KeyCode::Char('r') | KeyCode::Enter => {
+                        game = new_game(registry, solver, size);
+                        turn = None;
+                    },
+                    KeyCode::Char('+') => interval = (interval / 2).max(MIN_INTERVAL),
+                    KeyCode::Char('-') => interval = interval.saturating_mul(2).min(MAX_INTERVAL),
+                    KeyCode::Up => turn = Some(Direction::Up),
+                    KeyCode::Down => turn = Some(Direction::Down),
+                    KeyCode::Left => turn = Some(Direction::Left),
+                    KeyCode::Right => turn = Some(Direction::Right),
+                    _ => (),
+                },
+                Event::Resize(..) => queue!(out, terminal::Clear(ClearType::All))?,
+                _ => (),
+            }
+
+            continue;
+        }
+
+        next_step = Instant::now() + interval;
+
+        if paused {
+            continue;
+        }
+
+        if solver.is_some() {
+            game.step();
+        } else {
+            let snake = game.snake();
+            let direction = turn
+                .take()
+                .filter(|&dir| snake.can_turn(dir))
+                .unwrap_or(snake.direction);
+            game.step_towards(direction);
+        }
+    }
+}
+
+fn new_game(registry: &SolverRegistry, solver: Option<&str>, size: UVec2) -> HeadlessGame {
+    let settings = Settings {
+        arena_size: size,
+        ..Default::default()
+    };
+    let solver = solver.and_then(|name| registry.create(name)).unwrap_or_default();
+
+    HeadlessGame::new(settings, solver)
+}
+
+/// Puts the terminal into raw mode on an alternate screen, and restores it once dropped, including
+/// when returning early with an error or unwinding from a panic.
+struct Terminal;
+
+impl Terminal {
+    fn enter() -> io::Result<Terminal> {
+        terminal::enable_raw_mode()?;
+        // Created right away, so that the terminal is restored if switching screens fails.
+        let guard = Self;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+
+        // Panic messages are printed before unwinding, so restore the terminal first, or the
+        // message would be lost along with the alternate screen.
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore();
+            hook(info);
+        }));
+
+        Ok(guard)
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        restore();
+
+        // Removing the hook panics while unwinding, by which point it has already run anyway.
+        if !thread::panicking() {
+            let _ = panic::take_hook();
+        }
+    }
+}
+
+fn restore() {
+    let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+}
+
+fn draw(out: &mut impl Write, game: &HeadlessGame, colors: &ColorSettings, status: &str) -> io::Result<()> {
+    let arena = game.arena();
+
+    queue!(out, SetBackgroundColor(color(colors.background_dark)))?;
+
+    // Terminal rows go down, while arena rows go up.
+    for (row, y) in (0..arena.size.y).rev().enumerate() {
+        queue!(out, cursor::MoveTo(0, row as u16))?;
+
+        for x in 0..arena.size.x {
+            let pos = UVec2::new(x, y);
+            let foreground = match arena.get_cell_unchecked(pos) {
+                Cell::None => style::Color::DarkGrey,
+                Cell::SnakeTail { .. } => color(colors.snake_tail),
+                Cell::SnakeHead => color(colors.snake_head),
+                Cell::Food => color(colors.food),
+            };
+
+            queue!(out, SetForegroundColor(foreground), Print(glyph(arena, pos)))?;
+        }
+    }
+
+    queue!(
+        out,
+        style::ResetColor,
+        cursor::MoveTo(0, arena.size.y as u16),
+        Print(status),
+        terminal::Clear(ClearType::FromCursorDown),
+    )?;
+
+    out.flush()
+}
+
+fn status(game: &HeadlessGame, solver: Option<&str>, paused: bool, interval: Duration) -> String {
+    let snake = game.snake();
+    let state = match game.outcome() {
+        Some(GameOutcome::Won) => "won",
+        Some(GameOutcome::Lost) => "lost",
+        Some(GameOutcome::Stalled) => "stalled",
+        None if paused => "paused",
+        None => "running",
+    };
+    let controls = match solver {
+        Some(_) => "space pauses, r restarts, +/- change speed, q quits",
+        None => "arrows steer, space pauses, r restarts, +/- change speed, q quits",
+    };
+
+    format!(
+        "{} {}  length {}  steps {}  {:?} per step\r\n{}",
+        solver.unwrap_or("human"),
+        state,
+        snake.length,
+        snake.steps,
+        interval,
+        controls
+    )
+}
+
+/// The two characters drawn for the cell at `pos`, which join up snake segments with box-drawing
+/// lines in the same way as [`update_cell`](crate::arena::update_cell) joins up its rectangles.
+fn glyph(arena: &Arena, pos: UVec2) -> &'static str {
+    match arena.get_cell_unchecked(pos) {
+        Cell::None => "· ",
+        Cell::Food => "● ",
+        Cell::SnakeHead => {
+            if arena.segment_neighbors(pos).right() {
+                "@─"
+            } else {
+                "@ "
+            }
+        },
+        Cell::SnakeTail { .. } => segment(arena.segment_neighbors(pos)),
+    }
+}
+
+/// A snake segment joined to its neighbors in `dirs`. Segments joined to the right extend into the
+/// second character, so that they meet the segment next to them.
+fn segment(dirs: Directions) -> &'static str {
+    match (dirs.up(), dirs.down(), dirs.left(), dirs.right()) {
+        (true, true, _, _) => "│ ",
+        (_, _, true, true) => "──",
+        (true, _, true, _) => "┘ ",
+        (true, _, _, true) => "└─",
+        (_, true, true, _) => "┐ ",
+        (_, true, _, true) => "┌─",
+        (true, _, _, _) => "╵ ",
+        (_, true, _, _) => "╷ ",
+        (_, _, true, _) => "╴ ",
+        (_, _, _, true) => "╶─",
+        _ => "■ ",
+    }
+}
+
+fn color(color: color::Color) -> style::Color {
+    let [r, g, b] = color.to_srgba().to_u8_array_no_alpha();
+    style::Color::Rgb { r, g, b }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+
+    fn row(arena: &Arena, y: u32) -> String {
+        (0..arena.size.x).map(|x| glyph(arena, UVec2::new(x, y))).collect()
+    }
+
+    #[test]
+    fn glyphs_join_snake_segments() {
+        let settings = Settings {
+            arena_size: UVec2::new(8, 4),
+            seed: Some(0),
+            ..Default::default()
+        };
+        let mut game = HeadlessGame::new(settings, Solver::default());
+
+        assert_eq!(row(game.arena(), 2), "· · · @───╴ · · ");
+
+        game.step_towards(Direction::Up);
+
+        assert_eq!(row(game.arena(), 3), "· · · @ · · · · ");
+        assert_eq!(row(game.arena(), 2), "· · · └─╴ · · · ");
+    }
+}
@@ -1,6 +1,9 @@
-use std::num::NonZeroU32;
+use std::{num::NonZeroU32, path::Path};
 
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::prelude::*;
+#[cfg(feature = "inspector")]
+use bevy::window::PrimaryWindow;
+#[cfg(feature = "inspector")]
 use bevy_inspector_egui::{
     bevy_egui::{EguiContext, EguiPlugin},
     bevy_inspector, egui,
@@ -10,13 +13,18 @@ use bevy_inspector_egui::{
 
 use crate::{
     arena::Arena,
-    game::GameState,
+    game::{GameRng, GameState},
+    profiling::SolverProfile,
     settings::Settings,
     snake::Snake,
-    solver::{astar::AstarSolver, greedy::GreedySolver, hamilton::HamiltonSolver, Solver},
+    solver::{hamilton::HamiltonSolver, Solver, SolverRegistry},
+    theme::{Theme, Themes},
 };
+#[cfg(feature = "inspector")]
+use crate::{game::Statistics, leaderboard::Leaderboard, profiling::Histogram};
 
 pub struct UiPlugin {
+    /// Show the inspector panel, if built with the `inspector` feature.
     pub inspector: bool,
 }
 
@@ -24,25 +32,53 @@ impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Configuration>()
             .register_type::<Configuration>()
-            .add_systems(Update, (update_settings, update_game_state, update_solver));
+            .add_systems(
+                Update,
+                (
+                    update_settings,
+                    update_game_state,
+                    update_solver,
+                    next_theme,
+                    update_theme.after(next_theme),
+                    save_cycle,
+                ),
+            );
 
+        #[cfg(feature = "inspector")]
         if self.inspector {
             app.add_systems(Update, update_ui)
                 .add_plugins((DefaultInspectorConfigPlugin, EguiPlugin));
         }
+
+        #[cfg(not(feature = "inspector"))]
+        if self.inspector {
+            warn!("the inspector panel needs the `inspector` feature");
+        }
     }
 }
 
-#[derive(Reflect, Resource, InspectorOptions)]
-#[reflect(Resource, InspectorOptions)]
+#[derive(Reflect, Resource)]
+#[cfg_attr(feature = "inspector", derive(InspectorOptions))]
+#[cfg_attr(feature = "inspector", reflect(Resource, InspectorOptions))]
+#[cfg_attr(not(feature = "inspector"), reflect(Resource))]
 pub struct Configuration {
     pub debug_adjacencies: bool,
     pub debug_solver_tables: bool,
     pub debug_solver_paths: bool,
     pub debug_solver_points: bool,
-    solver: SolverVariant,
+    pub debug_analysis: bool,
+    /// The name of the solver in the [`SolverRegistry`], picked from a list in the panel.
+    #[reflect(ignore)]
+    pub solver: &'static str,
+    /// The name of the theme in [`Themes`], picked from a list in the panel or cycled through with
+    /// the T key.
+    #[reflect(ignore)]
+    pub theme: String,
     interval: Option<f32>,
     substeps: u32,
+    interpolate: bool,
+    max_steps_without_food: Option<usize>,
+    seed: Option<u64>,
 }
 
 impl Default for Configuration {
@@ -52,21 +88,19 @@ impl Default for Configuration {
             debug_solver_tables: true,
             debug_solver_paths: true,
             debug_solver_points: false,
-            solver: SolverVariant::default(),
+            debug_analysis: false,
+            solver: "hamilton",
+            theme: String::from("flat"),
             interval: None,
             substeps: 1,
+            interpolate: false,
+            max_steps_without_food: None,
+            seed: None,
         }
     }
 }
 
-#[derive(Default, Reflect, PartialEq, Copy, Clone)]
-enum SolverVariant {
-    Astar,
-    Greedy,
-    #[default]
-    Hamilton,
-}
-
+#[cfg(feature = "inspector")]
 fn update_ui(world: &mut World) {
     let window = world.query_filtered::<&Window, With<PrimaryWindow>>().single(world);
     let window_size = window.size();
@@ -87,38 +121,247 @@ fn update_ui(world: &mut World) {
         .exact_width(width)
         .show(context.get_mut(), |ui| {
             bevy_inspector::ui_for_resource::<Configuration>(world, ui);
+            ui_for_solver_choice(world, ui);
+            ui_for_solver_settings(world, ui);
+            ui_for_cycle(world, ui);
+            ui_for_theme_choice(world, ui);
+            ui.separator();
+            bevy_inspector::ui_for_resource::<Statistics>(world, ui);
+
+            if let Some(profile) = world.get_resource::<SolverProfile>() {
+                ui.separator();
+                ui_for_profile(profile, ui);
+            }
+
+            if let Some(leaderboard) = world.get_resource::<Leaderboard>() {
+                ui.separator();
+                ui_for_leaderboard(leaderboard, ui);
+            }
         });
 }
 
+#[cfg(feature = "inspector")]
+fn ui_for_solver_choice(world: &mut World, ui: &mut egui::Ui) {
+    let Some(registry) = world.get_resource::<SolverRegistry>() else {
+        return;
+    };
+
+    let names = registry.names().collect::<Vec<_>>();
+    let mut config = world.resource_mut::<Configuration>();
+    let mut selected = config.solver;
+
+    egui::ComboBox::from_label("Solver")
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            for name in names {
+                ui.selectable_value(&mut selected, name, name);
+            }
+        });
+
+    if selected != config.solver {
+        config.solver = selected;
+    }
+}
+
+#[cfg(feature = "inspector")]
+fn ui_for_theme_choice(world: &mut World, ui: &mut egui::Ui) {
+    let Some(themes) = world.get_resource::<Themes>() else {
+        return;
+    };
+
+    let names = themes.names().map(String::from).collect::<Vec<_>>();
+    let mut config = world.resource_mut::<Configuration>();
+    let mut selected = config.theme.clone();
+
+    egui::ComboBox::from_label("Theme")
+        .selected_text(&selected)
+        .show_ui(ui, |ui| {
+            for name in names {
+                ui.selectable_value(&mut selected, name.clone(), name);
+            }
+        });
+
+    if selected != config.theme {
+        config.theme = selected;
+    }
+}
+
+/// Shows the settings of the current solver, which it picks up on its next move.
+#[cfg(feature = "inspector")]
+fn ui_for_solver_settings(world: &mut World, ui: &mut egui::Ui) {
+    if !world.contains_resource::<Solver>() {
+        return;
+    }
+
+    world.resource_scope::<Solver, _>(|world, mut solver| {
+        let Some(settings) = solver.bypass_change_detection().settings_mut() else {
+            return;
+        };
+
+        if bevy_inspector::ui_for_value(settings, ui, world) {
+            solver.set_changed();
+        }
+    });
+}
+
+/// Offers to save the cycle of the current solver, if it follows one.
+#[cfg(feature = "inspector")]
+fn ui_for_cycle(world: &mut World, ui: &mut egui::Ui) {
+    let (Some(solver), Some(settings)) = (world.get_resource::<Solver>(), world.get_resource::<Settings>()) else {
+        return;
+    };
+
+    if solver.downcast_ref::<HamiltonSolver>().is_none() {
+        return;
+    }
+
+    let button = ui
+        .button("Save cycle")
+        .on_hover_text(format!("Writes the cycle to {}", settings.cycle_path.display()));
+
+    if button.clicked() {
+        save_solver_cycle(solver, &settings.cycle_path);
+    }
+}
+
+#[cfg(feature = "inspector")]
+fn ui_for_profile(profile: &SolverProfile, ui: &mut egui::Ui) {
+    ui.heading("Solver Profile");
+
+    let histograms = [
+        ("Decision time (µs)", &profile.decision_micros),
+        ("A* node expansions", &profile.astar_expansions),
+        ("Longest path extensions", &profile.longest_path_extensions),
+    ];
+
+    for (name, histogram) in histograms {
+        ui.collapsing(name, |ui| ui_for_histogram(name, histogram, ui));
+    }
+}
+
+#[cfg(feature = "inspector")]
+fn ui_for_histogram(name: &str, histogram: &Histogram, ui: &mut egui::Ui) {
+    ui.label(format!(
+        "mean {:.1}, p50 ≤ {}, p99 ≤ {}, max {}",
+        histogram.mean(),
+        histogram.quantile(0.5),
+        histogram.quantile(0.99),
+        histogram.max()
+    ));
+
+    let count = histogram.count().max(1) as f32;
+
+    egui::Grid::new(name).show(ui, |ui| {
+        for (low, high, n) in histogram.buckets() {
+            ui.label(format!("{}..={}", low, high));
+            ui.add(egui::ProgressBar::new(n as f32 / count).text(n.to_string()));
+            ui.end_row();
+        }
+    });
+}
+
+#[cfg(feature = "inspector")]
+fn ui_for_leaderboard(leaderboard: &Leaderboard, ui: &mut egui::Ui) {
+    ui.heading("Leaderboard");
+
+    egui::Grid::new("leaderboard").striped(true).show(ui, |ui| {
+        for header in ["Arena", "Solver", "Mode", "Score", "Date", "Seed"] {
+            ui.strong(header);
+        }
+        ui.end_row();
+
+        for entry in leaderboard.entries() {
+            ui.label(format!("{}x{}", entry.arena_size.x, entry.arena_size.y));
+            ui.label(&entry.solver);
+            ui.label(format!("{:?}", entry.mode));
+            ui.label(entry.score.to_string());
+            ui.label(&entry.date);
+            ui.label(entry.seed.to_string());
+            ui.end_row();
+        }
+    });
+}
+
 fn update_settings(mut settings: ResMut<Settings>, config: Res<Configuration>) {
     settings.interval = config.interval;
     settings.substeps = NonZeroU32::new(config.substeps).unwrap_or(NonZeroU32::new(1).unwrap());
+    settings.interpolate = config.interpolate;
+    settings.max_steps_without_food = config.max_steps_without_food;
+    settings.seed = config.seed;
 }
 
 fn update_solver(
     mut solver: ResMut<Solver>,
+    mut profile: ResMut<SolverProfile>,
     config: Res<Configuration>,
+    registry: Res<SolverRegistry>,
     snake: Res<Snake>,
     arena: Res<Arena>,
-    mut last_solver: Local<SolverVariant>,
+    rng: Res<GameRng>,
 ) {
-    if !config.is_changed() {
+    if !config.is_changed() || config.solver == solver.name() {
         return;
     }
 
-    if config.solver == *last_solver {
+    let Some(new_solver) = registry.create(config.solver) else {
+        warn!("unknown solver `{}`", config.solver);
         return;
+    };
+
+    *solver = new_solver;
+    solver.initialize(&snake, &arena, rng.seed());
+    *profile = SolverProfile::default();
+}
+
+fn update_theme(mut theme: ResMut<Theme>, themes: Option<Res<Themes>>, config: Res<Configuration>) {
+    let Some(themes) = themes else {
+        return;
+    };
+
+    if !config.is_changed() || config.theme == theme.name() {
+        return;
+    }
+
+    match themes.get(&config.theme) {
+        Some(new_theme) => *theme = new_theme.clone(),
+        None => warn!("unknown theme `{}`", config.theme),
+    }
+}
+
+/// Switches to the next theme when T is pressed.
+fn next_theme(keys: Res<ButtonInput<KeyCode>>, themes: Option<Res<Themes>>, mut config: ResMut<Configuration>) {
+    let Some(themes) = themes else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::KeyT) {
+        let names = themes.names().collect::<Vec<_>>();
+        let next = names.iter().position(|&name| name == config.theme).map_or(0, |i| i + 1);
+        config.theme = names[next % names.len()].to_string();
     }
+}
 
-    *last_solver = config.solver;
+/// Saves the cycle of the current solver to [`Settings::cycle_path`] when C is pressed.
+fn save_cycle(keys: Res<ButtonInput<KeyCode>>, solver: Option<Res<Solver>>, settings: Res<Settings>) {
+    let Some(solver) = solver else {
+        return;
+    };
 
-    match config.solver {
-        SolverVariant::Astar => *solver = Solver::Astar(AstarSolver::default()),
-        SolverVariant::Greedy => *solver = Solver::Greedy(GreedySolver::default()),
-        SolverVariant::Hamilton => *solver = Solver::Hamilton(HamiltonSolver::default()),
+    if keys.just_pressed(KeyCode::KeyC) {
+        save_solver_cycle(&solver, &settings.cycle_path);
     }
+}
 
-    solver.initialize(&snake, &arena);
+fn save_solver_cycle(solver: &Solver, path: &Path) {
+    let Some(hamilton) = solver.downcast_ref::<HamiltonSolver>() else {
+        warn!("the {} solver doesn't follow a cycle to save", solver.name());
+        return;
+    };
+
+    match hamilton.save_cycle(path) {
+        Ok(()) => info!("saved the cycle to {}", path.display()),
+        Err(e) => error!("failed to save the cycle to {}: {}", path.display(), e),
+    }
 }
 
 fn update_game_state(
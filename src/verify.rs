@@ -0,0 +1,251 @@
+use std::panic::{self, AssertUnwindSafe};
+
+use bevy::prelude::*;
+
+use crate::{
+    game::GameOutcome,
+    headless::HeadlessGame,
+    settings::Settings,
+    solver::{cycle, hamilton::HamiltonSolver, Solver},
+};
+
+/// Plays a full game with a [`HamiltonSolver`] and checks that it wins, returning the number of
+/// steps it took.
+///
+/// After every step, the body of the snake must still lie along the cycle in order from tail to
+/// head, since any shortcut which breaks that order can lead the snake into itself later on.
+pub fn verify_hamilton(size: UVec2, seed: u64) -> Result<usize, String> {
+    let settings = Settings {
+        arena_size: size,
+        seed: Some(seed),
+        ..Default::default()
+    };
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut game = HeadlessGame::new(settings, Solver::new("hamilton", HamiltonSolver::default()));
+
+        let Some(solver) = game.solver().downcast_ref::<HamiltonSolver>() else {
+            unreachable!()
+        };
+        cycle::validate(size, solver.cycle())?;
+
+        loop {
+            let outcome = game.step();
+            check_body_order(&game)?;
+
+            match outcome {
+                None => (),
+                Some(GameOutcome::Won) => return Ok(game.snake().steps),
+                Some(outcome) => {
+                    return Err(format!(
+                        "{:?} after {} steps at length {}\n{:?}",
+                        outcome,
+                        game.snake().steps,
+                        game.snake().length,
+                        game.arena()
+                    ))
+                },
+            }
+        }
+    }));
+
+    result.unwrap_or_else(|e| {
+        let message = e
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| e.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+
+        Err(format!("panicked: {}", message))
+    })
+}
+
+fn check_body_order(game: &HeadlessGame) -> Result<(), String> {
+    let Some(solver) = game.solver().downcast_ref::<HamiltonSolver>() else {
+        unreachable!()
+    };
+
+    let arena = game.arena();
+    let cycle = solver.cycle();
+    let area = cycle.len();
+    let index = |pos: UVec2| cycle[(pos.y * arena.size.x + pos.x) as usize].index;
+
+    let tail = index(arena.tail);
+    let mut previous = 0;
+
+    for &pos in arena.body.iter().rev().skip(1) {
+        let distance = (index(pos) + area - tail) % area;
+
+        if distance <= previous {
+            return Err(format!(
+                "body left the cycle at {} after {} steps\n{:?}",
+                pos,
+                game.snake().steps,
+                arena
+            ));
+        }
+
+        previous = distance;
+    }
+
+    Ok(())
+}
+
+/// Runs [`verify_hamilton`] on every arena with even width and height from 4x4 up to
+/// `max_size`, with `seeds` seeds each, printing a line per arena size.
+///
+/// Returns whether every game was won.
+pub fn run(max_size: u32, seeds: u64) -> bool {
+    let mut ok = true;
+
+    for width in (4..=max_size).step_by(2) {
+        for height in (4..=max_size).step_by(2) {
+            let size = UVec2::new(width, height);
+            let mut steps = Vec::new();
+            let mut failures = Vec::new();
+
+            for seed in 0..seeds {
+                match verify_hamilton(size, seed) {
+                    Ok(s) => steps.push(s),
+                    Err(e) => failures.push((seed, e)),
+                }
+            }
+
+            if failures.is_empty() {
+                let mean = steps.iter().sum::<usize>() as f64 / steps.len().max(1) as f64;
+                println!("{}x{}: ok, {:.0} steps on average", width, height, mean);
+            } else {
+                ok = false;
+                println!("{}x{}: {} of {} seeds failed", width, height, failures.len(), seeds);
+
+                for (seed, e) in failures {
+                    println!("  seed {}: {}", seed, e);
+                }
+            }
+        }
+    }
+
+    ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hamilton_wins_small_arenas() {
+        for width in (4..=10).step_by(2) {
+            for height in (4..=10).step_by(2) {
+                for seed in 0..3 {
+                    let size = UVec2::new(width, height);
+
+                    if let Err(e) = verify_hamilton(size, seed) {
+                        panic!("{}x{} with seed {}: {}", width, height, seed, e);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hamilton_cycles_differ_between_seeds() {
+        let size = UVec2::new(8, 8);
+        let cycles = (0..4)
+            .map(|seed| {
+                let settings = Settings {
+                    arena_size: size,
+                    seed: Some(seed),
+                    ..Default::default()
+                };
+                let game = HeadlessGame::new(settings, Solver::new("hamilton", HamiltonSolver::default()));
+                let Some(solver) = game.solver().downcast_ref::<HamiltonSolver>() else {
+                    unreachable!()
+                };
+
+                solver.cycle().iter().map(|cell| cell.direction).collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        for (i, cycle) in cycles.iter().enumerate() {
+            assert!(
+                cycles[i + 1..].iter().all(|other| other != cycle),
+                "seed {} repeats a cycle",
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn hamilton_follows_loaded_cycle() {
+        let size = UVec2::new(6, 6);
+        let path = std::env::temp_dir().join(format!("snake-cycle-{}.txt", std::process::id()));
+        let settings = || Settings {
+            arena_size: size,
+            seed: Some(7),
+            ..Default::default()
+        };
+
+        let game = HeadlessGame::new(settings(), Solver::new("hamilton", HamiltonSolver::default()));
+        let Some(exported) = game.solver().downcast_ref::<HamiltonSolver>() else {
+            unreachable!()
+        };
+        exported.save_cycle(&path).unwrap();
+
+        let mut solver = HamiltonSolver::default();
+        solver.settings.random_cycle = false;
+        solver.settings.cycle_file = Some(path.clone());
+        let loaded_game = HeadlessGame::new(settings(), Solver::new("hamilton", solver));
+        std::fs::remove_file(&path).unwrap();
+
+        let Some(loaded) = loaded_game.solver().downcast_ref::<HamiltonSolver>() else {
+            unreachable!()
+        };
+        for (a, b) in exported.cycle().iter().zip(loaded.cycle()) {
+            assert_eq!(a.direction, b.direction);
+        }
+
+        let mut game = loaded_game;
+        while game.step().is_none() {}
+        assert_eq!(game.step(), Some(GameOutcome::Won));
+    }
+
+    #[test]
+    fn hamilton_handles_every_arena_size() {
+        for width in 3..=10 {
+            for height in 2..=10 {
+                for seed in 0..3 {
+                    let size = UVec2::new(width, height);
+                    let settings = Settings {
+                        arena_size: size,
+                        seed: Some(seed),
+                        ..Default::default()
+                    };
+                    let mut game = HeadlessGame::new(settings, Solver::new("hamilton", HamiltonSolver::default()));
+                    let Some(solver) = game.solver().downcast_ref::<HamiltonSolver>() else {
+                        unreachable!()
+                    };
+
+                    if HamiltonSolver::supports(size) {
+                        if let Err(e) = cycle::validate(size, solver.cycle()) {
+                            panic!("{}x{} with seed {}: {}", width, height, seed, e);
+                        }
+                    } else {
+                        assert!(
+                            solver.cycle().is_empty(),
+                            "{}x{} with seed {} has a cycle",
+                            width,
+                            height,
+                            seed
+                        );
+                    }
+
+                    for _ in 0..200 {
+                        if game.step().is_some() {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}